@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap, 
+    rc::Rc,
     sync::atomic::{
         AtomicUsize, 
         Ordering
@@ -14,12 +15,25 @@ use crate::{ast::{
     ApplicationNode, 
     ArithmeticNode, 
     LetNode,
+    LetRecNode,
+    LetTupleNode,
+    ConditionalNode,
+    TupleNode,
+    ProjectionNode,
+    ListNode,
     SyntaxTreeVisitor
 }, beta::NormalExpressionNode};
 
+///
+/// Alpha Converter
+/// 
+/// Renames every binder to a fresh name (@x0, @x1, ...), remembering the identifier each
+/// fresh name stands for so that expressions can be shown with the names the user wrote.
+/// 
 pub struct AlphaConverter {
     variable_scope_stack: HashMap<String, Vec<String>>,
     variable_index: AtomicUsize,
+    original_names: HashMap<String, String>,
 }
 
 impl AlphaConverter {
@@ -27,6 +41,7 @@ impl AlphaConverter {
         AlphaConverter {
             variable_scope_stack: HashMap::new(),
             variable_index: AtomicUsize::new(0),
+            original_names: HashMap::new(),
         }
     }
 
@@ -36,14 +51,16 @@ impl AlphaConverter {
         scope.last().unwrap().clone()
     }
 
-    pub fn is_bound(&self, name: &String) -> bool {
-        self.variable_scope_stack.contains_key(name) && self.variable_scope_stack.get(name).unwrap().len() > 0
+    pub fn is_bound(&self, name: &str) -> bool {
+        self.variable_scope_stack.get(name).is_some_and(|scope| !scope.is_empty())
     }
 
-    pub fn bind(&mut self, name: &String) {
+    pub fn bind(&mut self, name: &str) {
         let variable = self.generate();
         
-        let scope = self.variable_scope_stack.entry(name.clone()).or_insert(Vec::new());
+        self.original_names.insert(variable.clone(), name.to_string());
+
+        let scope = self.variable_scope_stack.entry(name.to_string()).or_default();
 
         scope.push(variable);
     }
@@ -65,6 +82,176 @@ impl AlphaConverter {
 
         true
     }
+
+    ///
+    /// The identifier a variable was written as. Binders renamed apart during reduction 
+    /// are primed fresh names, and fresh names made for no identifier are shown as x. Any
+    /// other name is a free variable as the user wrote it, primes included.
+    /// 
+    pub fn original_name(&self, name: &str) -> String {
+        if !name.starts_with('@') {
+            return name.to_string();
+        }
+
+        let base = name.trim_end_matches('\'');
+
+        match self.original_names.get(base) {
+            Some(original) => original.clone(),
+            None => String::from("x"),
+        }
+    }
+
+    ///
+    /// Rename the binders of an expression back to the identifiers they were written as.
+    /// A binder is primed (x, x', x'', ...) only when its name would otherwise capture a 
+    /// variable free in its scope, so the result is α-equivalent to the expression given.
+    /// 
+    pub fn restore(&self, node: &NormalExpressionNode) -> NormalExpressionNode {
+        self.restore_within(node, &mut Vec::new())
+    }
+
+    fn restore_within(&self, node: &NormalExpressionNode, scope: &mut Vec<(String, String)>) -> NormalExpressionNode {
+        match node {
+            NormalExpressionNode::Variable(name) => {
+                NormalExpressionNode::Variable(self.displayed_name(name, scope))
+            },
+            NormalExpressionNode::Constant(_) => {
+                node.clone()
+            },
+            NormalExpressionNode::Abstraction(parameter, body, curried) => {
+                let name = self.restore_binder(parameter, body, scope);
+                let body = self.restore_within(body.as_ref(), scope);
+                scope.pop();
+
+                NormalExpressionNode::Abstraction(name, Rc::new(body), *curried)
+            },
+            NormalExpressionNode::Application(function, argument) => {
+                NormalExpressionNode::Application(
+                    Rc::new(self.restore_within(function.as_ref(), scope)),
+                    Rc::new(self.restore_within(argument.as_ref(), scope)))
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                NormalExpressionNode::Arithmetic(
+                    Rc::new(self.restore_within(lhs.as_ref(), scope)),
+                    operator.clone(),
+                    Rc::new(self.restore_within(rhs.as_ref(), scope)))
+            },
+            NormalExpressionNode::Let(parameter, expression, body, function) => {
+                let expression = self.restore_within(expression.as_ref(), scope);
+
+                let name = self.restore_binder(parameter, body, scope);
+                let body = self.restore_within(body.as_ref(), scope);
+                scope.pop();
+
+                NormalExpressionNode::Let(name, Rc::new(expression), Rc::new(body), *function)
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                // The binders are in scope of one another, so none may take another's name
+                let mut taken = self.displayed_free_variables(node, scope);
+                let mut names = Vec::new();
+
+                for (parameter, _, _) in bindings {
+                    let name = self.choose_name(parameter, &taken);
+
+                    taken.push(name.clone());
+                    scope.push((parameter.clone(), name.clone()));
+                    names.push(name);
+                }
+
+                let expressions: Vec<(Rc<NormalExpressionNode>, bool)> = bindings.iter()
+                    .map(|(_, expression, function)| (Rc::new(self.restore_within(expression.as_ref(), scope)), *function))
+                    .collect();
+                let body = self.restore_within(body.as_ref(), scope);
+                scope.truncate(scope.len() - bindings.len());
+
+                let bindings = names.into_iter().zip(expressions)
+                    .map(|(name, (expression, function))| (name, expression, function))
+                    .collect();
+
+                NormalExpressionNode::LetRec(bindings, Rc::new(body))
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                let expression = self.restore_within(expression.as_ref(), scope);
+
+                // The variables of a pattern are in scope together, so none may take another's name
+                let mut taken: Vec<String> = body.free_variables().iter()
+                    .filter(|variable| !parameters.contains(variable))
+                    .map(|variable| self.displayed_name(variable, scope))
+                    .collect();
+                let mut names = Vec::new();
+
+                for parameter in parameters {
+                    let name = self.choose_name(parameter, &taken);
+
+                    taken.push(name.clone());
+                    scope.push((parameter.clone(), name.clone()));
+                    names.push(name);
+                }
+
+                let body = self.restore_within(body.as_ref(), scope);
+                scope.truncate(scope.len() - parameters.len());
+
+                NormalExpressionNode::LetTuple(names, Rc::new(expression), Rc::new(body))
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                NormalExpressionNode::Tuple(elements.iter()
+                    .map(|element| Rc::new(self.restore_within(element.as_ref(), scope)))
+                    .collect())
+            },
+            NormalExpressionNode::Projection(tuple, index) => {
+                NormalExpressionNode::Projection(Rc::new(self.restore_within(tuple.as_ref(), scope)), *index)
+            },
+            NormalExpressionNode::Cons(head, tail) => {
+                NormalExpressionNode::Cons(
+                    Rc::new(self.restore_within(head.as_ref(), scope)),
+                    Rc::new(self.restore_within(tail.as_ref(), scope)))
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                NormalExpressionNode::Conditional(
+                    Rc::new(self.restore_within(condition.as_ref(), scope)),
+                    Rc::new(self.restore_within(consequent.as_ref(), scope)),
+                    Rc::new(self.restore_within(alternative.as_ref(), scope)))
+            },
+        }
+    }
+
+    // The name shown for a variable: that of its binder, or its original if it is free
+    fn displayed_name(&self, name: &str, scope: &[(String, String)]) -> String {
+        match scope.iter().rev().find(|(variable, _)| variable == name) {
+            Some((_, displayed)) => displayed.clone(),
+            None => self.original_name(name),
+        }
+    }
+
+    // Choose the name shown for a binder and bring it into scope
+    fn restore_binder(&self, parameter: &str, body: &NormalExpressionNode, scope: &mut Vec<(String, String)>) -> String {
+        let taken: Vec<String> = body.free_variables().iter()
+            .filter(|variable| variable.as_str() != parameter)
+            .map(|variable| self.displayed_name(variable, scope))
+            .collect();
+
+        let name = self.choose_name(parameter, &taken);
+
+        scope.push((parameter.to_string(), name.clone()));
+        name
+    }
+
+    // The names shown for the free variables of an expression
+    fn displayed_free_variables(&self, node: &NormalExpressionNode, scope: &[(String, String)]) -> Vec<String> {
+        node.free_variables().iter()
+            .map(|variable| self.displayed_name(variable, scope))
+            .collect()
+    }
+
+    // The original name of a binder, primed until it is not among the names taken
+    fn choose_name(&self, parameter: &str, taken: &[String]) -> String {
+        let mut name = self.original_name(parameter);
+        while taken.contains(&name) {
+            name.push('\'');
+        }
+
+        name
+    }
 }
 
 impl SyntaxTreeVisitor for AlphaConverter {
@@ -98,6 +285,25 @@ impl SyntaxTreeVisitor for AlphaConverter {
             ExpressionNode::Let(node) => {
                 self.visit_let(node);
             },
+            ExpressionNode::LetRec(node) => {
+                self.visit_let_rec(node);
+            },
+            ExpressionNode::LetTuple(node) => {
+                self.visit_let_tuple(node);
+            },
+            ExpressionNode::Conditional(node) => {
+                self.visit_conditional(node);
+            },
+            ExpressionNode::Tuple(node) => {
+                self.visit_tuple(node);
+            },
+            ExpressionNode::Projection(node) => {
+                self.visit_projection(node);
+            },
+            ExpressionNode::List(node) => {
+                self.visit_list(node);
+            },
+            ExpressionNode::Error(_) => { },
         }
     }
 
@@ -129,4 +335,69 @@ impl SyntaxTreeVisitor for AlphaConverter {
         node.expression.accept(self);
         node.scope.accept(self);
     }
+
+    fn visit_let_rec(&mut self, node: &LetRecNode) {
+        let names: Vec<String> = node.bindings.iter()
+            .map(|binding| binding.variable.name.borrow().clone())
+            .collect();
+
+        // Every binding is in scope before any bound expression is visited
+        for name in &names {
+            self.bind(name);
+        }
+
+        for binding in &node.bindings {
+            binding.variable.accept(self);
+            binding.expression.accept(self);
+        }
+        node.scope.accept(self);
+
+        for name in names.iter().rev() {
+            self.release(name);
+        }
+    }
+
+    fn visit_let_tuple(&mut self, node: &LetTupleNode) {
+        let names: Vec<String> = node.variables.iter()
+            .map(|variable| variable.name.borrow().clone())
+            .collect();
+
+        // The tuple is outside the scope of its pattern
+        node.expression.accept(self);
+
+        for name in &names {
+            self.bind(name);
+        }
+
+        for variable in &node.variables {
+            variable.accept(self);
+        }
+        node.scope.accept(self);
+
+        for name in names.iter().rev() {
+            self.release(name);
+        }
+    }
+
+    fn visit_tuple(&mut self, node: &TupleNode) {
+        for element in &node.elements {
+            element.accept(self);
+        }
+    }
+
+    fn visit_projection(&mut self, node: &ProjectionNode) {
+        node.tuple.accept(self);
+    }
+
+    fn visit_list(&mut self, node: &ListNode) {
+        for element in &node.elements {
+            element.accept(self);
+        }
+    }
+
+    fn visit_conditional(&mut self, node: &ConditionalNode) {
+        node.condition.accept(self);
+        node.consequent.accept(self);
+        node.alternative.accept(self);
+    }
 }
\ No newline at end of file
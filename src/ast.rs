@@ -1,4 +1,6 @@
-use std::{rc::Rc, cell::RefCell};
+use std::{rc::Rc, cell::RefCell, fmt};
+
+use crate::diagnostic::Span;
 
 ///
 /// Visitor Pattern trait for abstract syntax tree
@@ -11,6 +13,21 @@ pub trait SyntaxTreeVisitor {
     fn visit_application(&mut self, node: &ApplicationNode);
     fn visit_arithmetic(&mut self, node: &ArithmeticNode);
     fn visit_let(&mut self, node: &LetNode);
+    fn visit_let_rec(&mut self, node: &LetRecNode);
+    fn visit_let_tuple(&mut self, node: &LetTupleNode);
+    fn visit_tuple(&mut self, node: &TupleNode);
+    fn visit_projection(&mut self, node: &ProjectionNode);
+    fn visit_list(&mut self, node: &ListNode);
+    fn visit_conditional(&mut self, node: &ConditionalNode);
+}
+
+///
+/// Statement: a top-level definition or an expression to evaluate
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    Definition(VariableNode, ExpressionNode),
+    Expression(ExpressionNode),
 }
 
 ///
@@ -24,12 +41,37 @@ pub enum ExpressionNode {
     Application(ApplicationNode),
     Arithmetic(ArithmeticNode),
     Let(LetNode),
+    LetRec(LetRecNode),
+    LetTuple(LetTupleNode),
+    Conditional(ConditionalNode),
+    Tuple(TupleNode),
+    Projection(ProjectionNode),
+    List(ListNode),
+    Error(ErrorNode),
 }
 
 impl ExpressionNode {
     pub fn accept(&self, visitor: &mut dyn SyntaxTreeVisitor) {
         visitor.visit_expression(self);
     }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ExpressionNode::Variable(node) => node.span,
+            ExpressionNode::Constant(node) => node.span,
+            ExpressionNode::Abstraction(node) => node.span,
+            ExpressionNode::Application(node) => node.span,
+            ExpressionNode::Arithmetic(node) => node.span,
+            ExpressionNode::Let(node) => node.span,
+            ExpressionNode::LetRec(node) => node.span,
+            ExpressionNode::LetTuple(node) => node.span,
+            ExpressionNode::Conditional(node) => node.span,
+            ExpressionNode::Tuple(node) => node.span,
+            ExpressionNode::Projection(node) => node.span,
+            ExpressionNode::List(node) => node.span,
+            ExpressionNode::Error(node) => node.span,
+        }
+    }
 }
 
 
@@ -39,12 +81,14 @@ impl ExpressionNode {
 #[derive(Debug, PartialEq, Clone)]
 pub struct VariableNode {
     pub name: Rc<RefCell<String>>,
+    pub span: Span,
 }
 
 impl VariableNode {
     pub fn new(name: &str) -> VariableNode {
         VariableNode {
             name: Rc::new(RefCell::new(name.to_string())),
+            span: Span::default(),
         }
     }
     
@@ -58,12 +102,162 @@ impl VariableNode {
 }
 
 
+///
+/// Constant
+/// 
+/// The values built into the language, and the functions that are primitives of it
+/// 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum Constant {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+    Character(char),
+    // The empty list
+    Nil,
+    Primitive(Primitive),
+}
+
+impl Constant {
+    // The kind of value, as named in type errors
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Constant::Integer(_) => "an integer",
+            Constant::Boolean(_) => "a boolean",
+            Constant::String(_) => "a string",
+            Constant::Character(_) => "a character",
+            Constant::Nil => "a list",
+            Constant::Primitive(_) => "a function",
+        }
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Integer(value) => write!(f, "{}", value),
+            Constant::Boolean(value) => write!(f, "{}", value),
+            Constant::String(value) => {
+                let escaped: String = value.chars().map(|chr| escape(chr, '"')).collect();
+                write!(f, "\"{}\"", escaped)
+            },
+            Constant::Character(value) => write!(f, "'{}'", escape(*value, '\'')),
+            Constant::Nil => write!(f, "[]"),
+            Constant::Primitive(primitive) => write!(f, "{}", primitive.name()),
+        }
+    }
+}
+
+///
+/// A character as written within quotes, escaped if it is the quote itself, a backslash or
+/// a control character (see lexer)
+/// 
+pub fn escape(chr: char, quote: char) -> String {
+    match chr {
+        '\n' => String::from("\\n"),
+        '\t' => String::from("\\t"),
+        '\r' => String::from("\\r"),
+        '\0' => String::from("\\0"),
+        '\\' => String::from("\\\\"),
+        chr if chr == quote => format!("\\{}", chr),
+        chr if chr.is_control() => format!("\\u{{{:x}}}", chr as u32),
+        chr => chr.to_string(),
+    }
+}
+
+///
+/// Primitive
+/// 
+/// Functions built into the language, written as keywords:
+/// 
+///     Fix     - the fixpoint of a function: fix g unfolds to g (fix g), but only on demand
+///     Fst     - the first component of a tuple, t.0
+///     Snd     - the second component of a tuple, t.1
+///     Cons    - the list with a head and a tail, cons h t is h :: t
+///     Head    - the first element of a list
+///     Tail    - a list without its first element
+///     IsNil   - whether a list is empty
+///     Map     - map f xs applies f to every element of xs
+///     Fold    - fold f z xs combines the elements of xs from the right: f x0 (f x1 (... z))
+///     Length  - the number of elements of a list, or of characters of a string
+///     Concat  - two strings joined: concat "ab" "c" is "abc"
+///     CharAt  - the character of a string at an index, from 0: charAt "abc" 1 is 'b'
+///     Show    - an integer or a boolean as a string: show 42 is "42"
+/// 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Primitive {
+    Fix,
+    Fst,
+    Snd,
+    Cons,
+    Head,
+    Tail,
+    IsNil,
+    Map,
+    Fold,
+    Length,
+    Concat,
+    CharAt,
+    Show,
+}
+
+impl Primitive {
+    pub const LISTS: [Primitive; 7] = [
+        Primitive::Cons,
+        Primitive::Head,
+        Primitive::Tail,
+        Primitive::IsNil,
+        Primitive::Map,
+        Primitive::Fold,
+        Primitive::Length,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Primitive::Fix => "fix",
+            Primitive::Fst => "fst",
+            Primitive::Snd => "snd",
+            Primitive::Cons => "cons",
+            Primitive::Head => "head",
+            Primitive::Tail => "tail",
+            Primitive::IsNil => "isnil",
+            Primitive::Map => "map",
+            Primitive::Fold => "fold",
+            Primitive::Length => "length",
+            Primitive::Concat => "concat",
+            Primitive::CharAt => "charAt",
+            Primitive::Show => "show",
+        }
+    }
+
+    // The number of arguments a primitive takes
+    pub fn arity(&self) -> usize {
+        match self {
+            Primitive::Cons | Primitive::Map | Primitive::Concat | Primitive::CharAt => 2,
+            Primitive::Fold => 3,
+            _ => 1,
+        }
+    }
+
+    // Whether a primitive needs the value of an argument, counted from 0, before it applies:
+    // the list the list primitives take, and every argument of the string primitives
+    pub fn is_strict(&self, index: usize) -> bool {
+        match self {
+            Primitive::Fix | Primitive::Fst | Primitive::Snd | Primitive::Cons => false,
+            Primitive::Concat | Primitive::CharAt | Primitive::Show => true,
+            _ => index + 1 == self.arity(),
+        }
+    }
+}
+
+
 ///
 /// Constant Node
 /// 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ConstantNode {
-    pub value: i64,
+    pub value: Constant,
+    pub span: Span,
 }
 
 impl ConstantNode {
@@ -76,10 +270,15 @@ impl ConstantNode {
 ///
 /// Visit Abstraction
 /// 
+/// An abstraction is curried when its body is an abstraction written in the same
+/// binder list, as \y is in \x y. e.
+/// 
 #[derive(Debug, PartialEq, Clone)]
 pub struct AbstractionNode {
     pub variable: Rc<VariableNode>,
     pub expression: Rc<ExpressionNode>,
+    pub curried: bool,
+    pub span: Span,
 }
 
 impl AbstractionNode {
@@ -96,6 +295,7 @@ impl AbstractionNode {
 pub struct ApplicationNode {
     pub function: Rc<ExpressionNode>,
     pub argument: Rc<ExpressionNode>,    
+    pub span: Span,
 }
 
 impl ApplicationNode {
@@ -113,6 +313,7 @@ pub struct ArithmeticNode {
     pub operator: String,
     pub left: Rc<ExpressionNode>,
     pub right: Rc<ExpressionNode>,
+    pub span: Span,
 }
 
 impl ArithmeticNode {
@@ -125,15 +326,126 @@ impl ArithmeticNode {
 ///
 /// Let Expression Node
 /// 
+/// A let is a function when the abstraction it binds was written as parameters of
+/// the let, as in let f x y = e in b.
+/// 
 #[derive(Debug, PartialEq, Clone)]
 pub struct LetNode {
     pub variable: VariableNode,
     pub expression: Rc<ExpressionNode>,
     pub scope: Rc<ExpressionNode>,
+    pub function: bool,
+    pub span: Span,
 }
 
 impl LetNode {
     // pub fn accept(&self, visitor: &mut dyn SyntaxTreeVisitor) {
     //     visitor.visit_let(self);
     // }
-}
\ No newline at end of file
+}
+
+
+///
+/// Recursive Let Expression Node
+/// 
+/// let rec f = e and g = e' in b: every variable bound is in scope in every bound 
+/// expression as well as in the scope, so the bindings may refer to each other.
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct LetRecNode {
+    pub bindings: Vec<BindingNode>,
+    pub scope: Rc<ExpressionNode>,
+    pub span: Span,
+}
+
+///
+/// Binding Node
+/// 
+/// A variable bound by a recursive let, which is a function when written with parameters
+/// (see LetNode).
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct BindingNode {
+    pub variable: VariableNode,
+    pub expression: Rc<ExpressionNode>,
+    pub function: bool,
+}
+
+
+///
+/// Tuple Let Expression Node
+/// 
+/// let (x, y) = e in b binds the variables of the pattern to the components of the 
+/// tuple e, which must have as many components as the pattern has variables.
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct LetTupleNode {
+    pub variables: Vec<VariableNode>,
+    pub expression: Rc<ExpressionNode>,
+    pub scope: Rc<ExpressionNode>,
+    pub span: Span,
+}
+
+
+///
+/// Conditional Node
+/// 
+/// if condition then consequent else alternative; only the branch chosen by the 
+/// condition is reduced.
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConditionalNode {
+    pub condition: Rc<ExpressionNode>,
+    pub consequent: Rc<ExpressionNode>,
+    pub alternative: Rc<ExpressionNode>,
+    pub span: Span,
+}
+
+
+///
+/// Tuple Node
+/// 
+/// (a, b, ...), with at least two components
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct TupleNode {
+    pub elements: Vec<Rc<ExpressionNode>>,
+    pub span: Span,
+}
+
+
+///
+/// Projection Node
+/// 
+/// t.0, t.1, ...: a component of a tuple, numbered from 0
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProjectionNode {
+    pub tuple: Rc<ExpressionNode>,
+    pub index: usize,
+    pub span: Span,
+}
+
+
+///
+/// List Node
+/// 
+/// [a, b, ...], with any number of elements, shorthand for a :: b :: ... :: []
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct ListNode {
+    pub elements: Vec<Rc<ExpressionNode>>,
+    pub span: Span,
+}
+
+
+///
+/// Error Node
+/// 
+/// Stands in for source text the parser could not make sense of, so that the rest of
+/// the input can still be parsed and checked.
+/// 
+#[derive(Debug, PartialEq, Clone)]
+pub struct ErrorNode {
+    pub span: Span,
+}
@@ -1,198 +1,2762 @@
-use std::rc::Rc;
-
-use crate::{
-    ast::ExpressionNode, 
-    // pretty::{
-    //     pretty_print_normal, 
-    //     pretty_print
-    // }
+use std::{
+    collections::{
+        hash_map::Entry,
+        HashMap,
+        HashSet
+    },
+    fmt,
+    hash::{
+        Hash,
+        Hasher
+    },
+    rc::Rc,
+    str::FromStr,
 };
 
-#[derive(Debug, PartialEq, Clone)]
+use crate::ast::{
+    Constant,
+    Primitive
+};
+use crate::debruijn::{
+    from_de_bruijn,
+    to_de_bruijn,
+    NamelessExpressionNode
+};
+use crate::encoding::ListEncoding;
+use crate::error::{
+    AlabastaError,
+    ArithmeticError
+};
+
+///
+/// Normal Expression Node
+/// 
+/// Expressions compare and hash equal when they are α-equivalent, that is when they
+/// would have the same nameless form (see debruijn), which is worked out without building it.
+/// 
+/// Abstractions keep whether they were written curried, and let bindings whether they
+/// were written as functions (see ast), so that results print the way they were written.
+/// Neither takes part in comparison.
+/// 
+#[derive(Debug, Clone)]
 pub enum NormalExpressionNode {
     Variable(String),
-    Constant(i64),
-    Abstraction(String, Rc<NormalExpressionNode>),
+    Constant(Constant),
+    Abstraction(String, Rc<NormalExpressionNode>, bool),
     Application(Rc<NormalExpressionNode>, Rc<NormalExpressionNode>),
     Arithmetic(Rc<NormalExpressionNode>, String, Rc<NormalExpressionNode>),
-    Let(String, Rc<NormalExpressionNode>, Rc<NormalExpressionNode>),
+    Let(String, Rc<NormalExpressionNode>, Rc<NormalExpressionNode>, bool),
+    LetRec(Vec<Binding>, Rc<NormalExpressionNode>),
+    LetTuple(Vec<String>, Rc<NormalExpressionNode>, Rc<NormalExpressionNode>),
+    Conditional(Rc<NormalExpressionNode>, Rc<NormalExpressionNode>, Rc<NormalExpressionNode>),
+    Tuple(Vec<Rc<NormalExpressionNode>>),
+    Projection(Rc<NormalExpressionNode>, usize),
+    Cons(Rc<NormalExpressionNode>, Rc<NormalExpressionNode>),
+}
+
+// A binding of a recursive let: the variable, the expression bound to it and whether it
+// was written as a function
+pub type Binding = (String, Rc<NormalExpressionNode>, bool);
+
+impl PartialEq for NormalExpressionNode {
+    fn eq(&self, other: &NormalExpressionNode) -> bool {
+        self.alpha_equivalent(other, &mut Vec::new())
+    }
+}
+
+impl Eq for NormalExpressionNode { }
+
+impl Hash for NormalExpressionNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.alpha_hash(&mut Vec::new(), state);
+    }
+}
+
+impl NormalExpressionNode {
+    // α-equivalence, walking both expressions together. A pair of variables is bound by the
+    // same pair of binders when both are found at the same place in the stack of binders
+    // in scope, innermost last, which is how they would get the same De Bruijn index.
+    fn alpha_equivalent<'a>(&'a self, other: &'a NormalExpressionNode, bound: &mut Vec<(&'a str, &'a str)>) -> bool {
+        match (self, other) {
+            (NormalExpressionNode::Variable(lhs), NormalExpressionNode::Variable(rhs)) => {
+                let left = bound.iter().rposition(|(name, _)| name == lhs);
+                let right = bound.iter().rposition(|(_, name)| name == rhs);
+
+                match (left, right) {
+                    (None, None) => lhs == rhs,
+                    (left, right) => left == right,
+                }
+            },
+            (NormalExpressionNode::Constant(lhs), NormalExpressionNode::Constant(rhs)) => lhs == rhs,
+            (NormalExpressionNode::Abstraction(lhs, lhs_body, _), NormalExpressionNode::Abstraction(rhs, rhs_body, _)) => {
+                bound.push((lhs, rhs));
+                let equivalent = lhs_body.alpha_equivalent(rhs_body, bound);
+                bound.pop();
+
+                equivalent
+            },
+            (NormalExpressionNode::Application(lhs_function, lhs_argument), NormalExpressionNode::Application(rhs_function, rhs_argument)) |
+            (NormalExpressionNode::Cons(lhs_function, lhs_argument), NormalExpressionNode::Cons(rhs_function, rhs_argument)) => {
+                lhs_function.alpha_equivalent(rhs_function, bound) && lhs_argument.alpha_equivalent(rhs_argument, bound)
+            },
+            (NormalExpressionNode::Arithmetic(lhs_left, lhs_operator, lhs_right), NormalExpressionNode::Arithmetic(rhs_left, rhs_operator, rhs_right)) => {
+                lhs_operator == rhs_operator &&
+                    lhs_left.alpha_equivalent(rhs_left, bound) &&
+                    lhs_right.alpha_equivalent(rhs_right, bound)
+            },
+            (NormalExpressionNode::Let(lhs, lhs_expression, lhs_body, _), NormalExpressionNode::Let(rhs, rhs_expression, rhs_body, _)) => {
+                if !lhs_expression.alpha_equivalent(rhs_expression, bound) {
+                    return false;
+                }
+
+                bound.push((lhs, rhs));
+                let equivalent = lhs_body.alpha_equivalent(rhs_body, bound);
+                bound.pop();
+
+                equivalent
+            },
+            (NormalExpressionNode::LetRec(lhs_bindings, lhs_body), NormalExpressionNode::LetRec(rhs_bindings, rhs_body)) => {
+                if lhs_bindings.len() != rhs_bindings.len() {
+                    return false;
+                }
+
+                let depth = bound.len();
+                bound.extend(lhs_bindings.iter().zip(rhs_bindings).map(|((lhs, _, _), (rhs, _, _))| (lhs.as_str(), rhs.as_str())));
+
+                let equivalent = lhs_bindings.iter().zip(rhs_bindings)
+                    .all(|((_, lhs, _), (_, rhs, _))| lhs.alpha_equivalent(rhs, bound)) &&
+                    lhs_body.alpha_equivalent(rhs_body, bound);
+
+                bound.truncate(depth);
+
+                equivalent
+            },
+            (NormalExpressionNode::LetTuple(lhs, lhs_expression, lhs_body), NormalExpressionNode::LetTuple(rhs, rhs_expression, rhs_body)) => {
+                if lhs.len() != rhs.len() || !lhs_expression.alpha_equivalent(rhs_expression, bound) {
+                    return false;
+                }
+
+                let depth = bound.len();
+                bound.extend(lhs.iter().zip(rhs).map(|(lhs, rhs)| (lhs.as_str(), rhs.as_str())));
+                let equivalent = lhs_body.alpha_equivalent(rhs_body, bound);
+                bound.truncate(depth);
+
+                equivalent
+            },
+            (NormalExpressionNode::Conditional(lhs_condition, lhs_consequent, lhs_alternative), NormalExpressionNode::Conditional(rhs_condition, rhs_consequent, rhs_alternative)) => {
+                lhs_condition.alpha_equivalent(rhs_condition, bound) &&
+                    lhs_consequent.alpha_equivalent(rhs_consequent, bound) &&
+                    lhs_alternative.alpha_equivalent(rhs_alternative, bound)
+            },
+            (NormalExpressionNode::Tuple(lhs), NormalExpressionNode::Tuple(rhs)) => {
+                lhs.len() == rhs.len() &&
+                    lhs.iter().zip(rhs).all(|(lhs, rhs)| lhs.alpha_equivalent(rhs, bound))
+            },
+            (NormalExpressionNode::Projection(lhs, lhs_index), NormalExpressionNode::Projection(rhs, rhs_index)) => {
+                lhs_index == rhs_index && lhs.alpha_equivalent(rhs, bound)
+            },
+            _ => false
+        }
+    }
+
+    // A hash consistent with α-equivalence: bound variables are hashed by their De Bruijn
+    // index, free variables by their name, and binders not at all
+    fn alpha_hash<'a, H: Hasher>(&'a self, bound: &mut Vec<&'a str>, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            NormalExpressionNode::Variable(name) => {
+                match bound.iter().rposition(|variable| variable == name) {
+                    Some(depth) => (bound.len() - 1 - depth).hash(state),
+                    None => name.hash(state),
+                }
+            },
+            NormalExpressionNode::Constant(value) => value.hash(state),
+            NormalExpressionNode::Abstraction(parameter, body, _) => {
+                bound.push(parameter);
+                body.alpha_hash(bound, state);
+                bound.pop();
+            },
+            NormalExpressionNode::Application(lhs, rhs) |
+            NormalExpressionNode::Cons(lhs, rhs) => {
+                lhs.alpha_hash(bound, state);
+                rhs.alpha_hash(bound, state);
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                operator.hash(state);
+                lhs.alpha_hash(bound, state);
+                rhs.alpha_hash(bound, state);
+            },
+            NormalExpressionNode::Let(parameter, expression, body, _) => {
+                expression.alpha_hash(bound, state);
+
+                bound.push(parameter);
+                body.alpha_hash(bound, state);
+                bound.pop();
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                let depth = bound.len();
+                bound.extend(bindings.iter().map(|(parameter, _, _)| parameter.as_str()));
+
+                bindings.len().hash(state);
+                for (_, expression, _) in bindings {
+                    expression.alpha_hash(bound, state);
+                }
+                body.alpha_hash(bound, state);
+
+                bound.truncate(depth);
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                parameters.len().hash(state);
+                expression.alpha_hash(bound, state);
+
+                let depth = bound.len();
+                bound.extend(parameters.iter().map(String::as_str));
+                body.alpha_hash(bound, state);
+                bound.truncate(depth);
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                condition.alpha_hash(bound, state);
+                consequent.alpha_hash(bound, state);
+                alternative.alpha_hash(bound, state);
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                elements.len().hash(state);
+                for element in elements {
+                    element.alpha_hash(bound, state);
+                }
+            },
+            NormalExpressionNode::Projection(tuple, index) => {
+                index.hash(state);
+                tuple.alpha_hash(bound, state);
+            },
+        }
+    }
+
+    ///
+    /// η-expansion: λx. e x, for a variable x that is not free in e
+    /// 
+    pub fn eta_expand(&self, parameter: &str) -> NormalExpressionNode {
+        NormalExpressionNode::Abstraction(
+            parameter.to_string(),
+            Rc::new(NormalExpressionNode::Application(
+                Rc::new(self.clone()),
+                Rc::new(NormalExpressionNode::Variable(parameter.to_string())))),
+            false)
+    }
+
+    ///
+    /// The variables occurring in an expression outside the scope of any binder for them
+    /// 
+    pub fn free_variables(&self) -> HashSet<String> {
+        let mut free = HashSet::new();
+
+        self.collect_free_variables(&mut Vec::new(), &mut free);
+
+        free
+    }
+
+    ///
+    /// Whether a variable occurs free in an expression; as free_variables().contains(variable),
+    /// without collecting the others
+    /// 
+    pub fn has_free(&self, variable: &str) -> bool {
+        match self {
+            NormalExpressionNode::Variable(name) => name == variable,
+            NormalExpressionNode::Constant(_) => false,
+            NormalExpressionNode::Abstraction(parameter, body, _) => {
+                parameter != variable && body.has_free(variable)
+            },
+            NormalExpressionNode::Application(lhs, rhs) |
+            NormalExpressionNode::Arithmetic(lhs, _, rhs) |
+            NormalExpressionNode::Cons(lhs, rhs) => {
+                lhs.has_free(variable) || rhs.has_free(variable)
+            },
+            NormalExpressionNode::Let(parameter, expression, body, _) => {
+                expression.has_free(variable) || (parameter != variable && body.has_free(variable))
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                !bindings.iter().any(|(parameter, _, _)| parameter == variable) &&
+                    (bindings.iter().any(|(_, expression, _)| expression.has_free(variable)) || body.has_free(variable))
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                expression.has_free(variable) || (!parameters.iter().any(|parameter| parameter == variable) && body.has_free(variable))
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                condition.has_free(variable) || consequent.has_free(variable) || alternative.has_free(variable)
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                elements.iter().any(|element| element.has_free(variable))
+            },
+            NormalExpressionNode::Projection(tuple, _) => {
+                tuple.has_free(variable)
+            },
+        }
+    }
+
+    fn collect_free_variables(&self, bound: &mut Vec<String>, free: &mut HashSet<String>) {
+        match self {
+            NormalExpressionNode::Variable(name) => {
+                if !bound.contains(name) {
+                    free.insert(name.clone());
+                }
+            },
+            NormalExpressionNode::Constant(_) => { },
+            NormalExpressionNode::Abstraction(parameter, body, _) => {
+                bound.push(parameter.clone());
+                body.collect_free_variables(bound, free);
+                bound.pop();
+            },
+            NormalExpressionNode::Application(lhs, rhs) |
+            NormalExpressionNode::Arithmetic(lhs, _, rhs) |
+            NormalExpressionNode::Cons(lhs, rhs) => {
+                lhs.collect_free_variables(bound, free);
+                rhs.collect_free_variables(bound, free);
+            },
+            NormalExpressionNode::Let(parameter, expression, body, _) => {
+                expression.collect_free_variables(bound, free);
+
+                bound.push(parameter.clone());
+                body.collect_free_variables(bound, free);
+                bound.pop();
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                bound.extend(bindings.iter().map(|(parameter, _, _)| parameter.clone()));
+
+                for (_, expression, _) in bindings {
+                    expression.collect_free_variables(bound, free);
+                }
+                body.collect_free_variables(bound, free);
+
+                bound.truncate(bound.len() - bindings.len());
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                expression.collect_free_variables(bound, free);
+
+                bound.extend(parameters.iter().cloned());
+                body.collect_free_variables(bound, free);
+                bound.truncate(bound.len() - parameters.len());
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                condition.collect_free_variables(bound, free);
+                consequent.collect_free_variables(bound, free);
+                alternative.collect_free_variables(bound, free);
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                for element in elements {
+                    element.collect_free_variables(bound, free);
+                }
+            },
+            NormalExpressionNode::Projection(tuple, _) => {
+                tuple.collect_free_variables(bound, free);
+            },
+        }
+    }
+
+    ///
+    /// Every name in an expression, of its variables, free or bound, and of its binders
+    /// 
+    pub fn names(&self) -> HashSet<String> {
+        let mut names = HashSet::new();
+
+        self.collect_names(&mut names);
+
+        names
+    }
+
+    fn collect_names(&self, names: &mut HashSet<String>) {
+        match self {
+            NormalExpressionNode::Variable(name) => {
+                names.insert(name.clone());
+            },
+            NormalExpressionNode::Constant(_) => { },
+            NormalExpressionNode::Abstraction(parameter, body, _) => {
+                names.insert(parameter.clone());
+                body.collect_names(names);
+            },
+            NormalExpressionNode::Application(lhs, rhs) |
+            NormalExpressionNode::Arithmetic(lhs, _, rhs) |
+            NormalExpressionNode::Cons(lhs, rhs) => {
+                lhs.collect_names(names);
+                rhs.collect_names(names);
+            },
+            NormalExpressionNode::Let(parameter, expression, body, _) => {
+                names.insert(parameter.clone());
+                expression.collect_names(names);
+                body.collect_names(names);
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                for (parameter, expression, _) in bindings {
+                    names.insert(parameter.clone());
+                    expression.collect_names(names);
+                }
+                body.collect_names(names);
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                names.extend(parameters.iter().cloned());
+                expression.collect_names(names);
+                body.collect_names(names);
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                condition.collect_names(names);
+                consequent.collect_names(names);
+                alternative.collect_names(names);
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                for element in elements {
+                    element.collect_names(names);
+                }
+            },
+            NormalExpressionNode::Projection(tuple, _) => {
+                tuple.collect_names(names);
+            },
+        }
+    }
+
+    ///
+    /// Number of nodes in an expression
+    /// 
+    pub fn size(&self) -> usize {
+        match self {
+            NormalExpressionNode::Variable(_) | NormalExpressionNode::Constant(_) => 1,
+            NormalExpressionNode::Abstraction(_, body, _) => 1 + body.size(),
+            NormalExpressionNode::Application(lhs, rhs) |
+            NormalExpressionNode::Arithmetic(lhs, _, rhs) |
+            NormalExpressionNode::Cons(lhs, rhs) => 1 + lhs.size() + rhs.size(),
+            NormalExpressionNode::Let(_, expression, body, _) => 1 + expression.size() + body.size(),
+            NormalExpressionNode::LetRec(bindings, body) => {
+                1 + bindings.iter().map(|(_, expression, _)| expression.size()).sum::<usize>() + body.size()
+            },
+            NormalExpressionNode::LetTuple(_, expression, body) => 1 + expression.size() + body.size(),
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                1 + condition.size() + consequent.size() + alternative.size()
+            },
+            NormalExpressionNode::Tuple(elements) => 1 + elements.iter().map(|element| element.size()).sum::<usize>(),
+            NormalExpressionNode::Projection(tuple, _) => 1 + tuple.size(),
+        }
+    }
+
+    ///
+    /// An expression cut short for display: subterms nested deeper than the given depth,
+    /// or met after the given number of nodes in left-to-right order, are replaced by …
+    /// 
+    pub fn elided(&self, depth: usize, nodes: usize) -> NormalExpressionNode {
+        let mut nodes = nodes;
+
+        self.elide_within(depth, &mut nodes)
+    }
+
+    fn elide_within(&self, depth: usize, nodes: &mut usize) -> NormalExpressionNode {
+        if depth == 0 || *nodes == 0 {
+            return NormalExpressionNode::Variable("…".to_string());
+        }
+
+        *nodes -= 1;
+
+        let mut elide = |node: &Rc<NormalExpressionNode>| Rc::new(node.elide_within(depth - 1, nodes));
+
+        match self {
+            NormalExpressionNode::Variable(_) | NormalExpressionNode::Constant(_) => self.clone(),
+            NormalExpressionNode::Abstraction(parameter, body, curried) => {
+                NormalExpressionNode::Abstraction(parameter.clone(), elide(body), *curried)
+            },
+            NormalExpressionNode::Application(function, argument) => {
+                NormalExpressionNode::Application(elide(function), elide(argument))
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                NormalExpressionNode::Arithmetic(elide(lhs), operator.clone(), elide(rhs))
+            },
+            NormalExpressionNode::Cons(head, tail) => {
+                NormalExpressionNode::Cons(elide(head), elide(tail))
+            },
+            NormalExpressionNode::Let(parameter, expression, body, function) => {
+                NormalExpressionNode::Let(parameter.clone(), elide(expression), elide(body), *function)
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                let bindings = bindings.iter()
+                    .map(|(parameter, expression, function)| (parameter.clone(), elide(expression), *function))
+                    .collect();
+
+                NormalExpressionNode::LetRec(bindings, elide(body))
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                NormalExpressionNode::LetTuple(parameters.clone(), elide(expression), elide(body))
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                NormalExpressionNode::Conditional(elide(condition), elide(consequent), elide(alternative))
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                NormalExpressionNode::Tuple(elements.iter().map(elide).collect())
+            },
+            NormalExpressionNode::Projection(tuple, index) => {
+                NormalExpressionNode::Projection(elide(tuple), *index)
+            },
+        }
+    }
+}
+
+///
+/// Reduction Strategy
+/// 
+/// Determines which redex is contracted next:
+/// 
+///     NormalOrder      - leftmost outermost redex first, reducing under abstractions (full normal form)
+///     ApplicativeOrder - leftmost innermost redex first, reducing under abstractions (full normal form)
+///     CallByName       - leftmost outermost redex first, arguments unevaluated (weak head normal form)
+///     CallByValue      - arguments evaluated before substitution (weak normal form)
+///     CallByNeed       - call-by-name where arguments are shared through let bindings and 
+///                        evaluated at most once (weak head normal form)
+/// 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ReductionStrategy {
+    #[default]
+    NormalOrder,
+    ApplicativeOrder,
+    CallByName,
+    CallByValue,
+    CallByNeed,
+}
+
+impl ReductionStrategy {
+    pub const ALL: [ReductionStrategy; 5] = [
+        ReductionStrategy::NormalOrder,
+        ReductionStrategy::ApplicativeOrder,
+        ReductionStrategy::CallByName,
+        ReductionStrategy::CallByValue,
+        ReductionStrategy::CallByNeed,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReductionStrategy::NormalOrder => "normal",
+            ReductionStrategy::ApplicativeOrder => "applicative",
+            ReductionStrategy::CallByName => "name",
+            ReductionStrategy::CallByValue => "value",
+            ReductionStrategy::CallByNeed => "need",
+        }
+    }
+
+    // Arguments are reduced before they are substituted
+    fn is_strict(&self) -> bool {
+        matches!(self, ReductionStrategy::ApplicativeOrder | ReductionStrategy::CallByValue)
+    }
+
+    // Abstraction bodies and arguments of stuck applications are reduced
+    fn is_full(&self) -> bool {
+        matches!(self, ReductionStrategy::NormalOrder | ReductionStrategy::ApplicativeOrder)
+    }
+}
+
+impl fmt::Display for ReductionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            ReductionStrategy::NormalOrder => "normal order",
+            ReductionStrategy::ApplicativeOrder => "applicative order",
+            ReductionStrategy::CallByName => "call-by-name",
+            ReductionStrategy::CallByValue => "call-by-value",
+            ReductionStrategy::CallByNeed => "call-by-need",
+        };
+
+        f.pad(description)
+    }
+}
+
+impl FromStr for ReductionStrategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<ReductionStrategy, String> {
+        match name {
+            "normal" | "normal-order" | "no" => Ok(ReductionStrategy::NormalOrder),
+            "applicative" | "applicative-order" | "ao" => Ok(ReductionStrategy::ApplicativeOrder),
+            "name" | "call-by-name" | "cbn" => Ok(ReductionStrategy::CallByName),
+            "value" | "call-by-value" | "cbv" => Ok(ReductionStrategy::CallByValue),
+            "need" | "call-by-need" | "lazy" => Ok(ReductionStrategy::CallByNeed),
+            _ => Err(format!("Unknown reduction strategy '{}'.", name)),
+        }
+    }
+}
+
+///
+/// Reduction Rule
+/// 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ReductionRule {
+    Beta,
+    Delta,
+    Let,
+    Recursion,
+    Eta,
+}
+
+impl fmt::Display for ReductionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ReductionRule::Beta => "β",
+            ReductionRule::Delta => "δ",
+            ReductionRule::Let => "let",
+            ReductionRule::Recursion => "rec",
+            ReductionRule::Eta => "η",
+        };
+
+        f.pad(symbol)
+    }
+}
+
+///
+/// Contraction
+/// 
+/// A single reduction step. The path locates the redex within the expression before the 
+/// contraction, as a sequence of child indices taken from the root (abstraction body: 0, 
+/// application function: 0, argument: 1, arithmetic lhs: 0, rhs: 1, let expression: 0, scope: 1,
+/// recursive let expressions: 0, 1, ..., scope: the number of bindings, conditional 
+/// condition: 0, consequent: 1, alternative: 2, tuple components: 0, 1, ..., projected 
+/// tuple: 0, cons head: 0, tail: 1).
+/// 
+#[derive(Debug, Clone)]
+pub struct Contraction {
+    pub before: NormalExpressionNode,
+    pub redex: NormalExpressionNode,
+    pub path: Vec<usize>,
+    pub rule: ReductionRule,
+    pub after: NormalExpressionNode,
+}
+
+// A contraction in progress, rebuilt bottom-up as the recursion unwinds
+struct Step {
+    redex: NormalExpressionNode,
+    path: Vec<usize>,
+    rule: ReductionRule,
+    result: NormalExpressionNode,
+}
+
+// The outcome of looking for the next contraction: none, if the expression is in normal 
+// form for the strategy, or an error if it is stuck on a term that can never make progress
+type Stepped = Result<Option<Step>, AlabastaError>;
+
+impl Step {
+    fn contract(rule: ReductionRule, redex: &NormalExpressionNode, result: Option<NormalExpressionNode>) -> Option<Step> {
+        Some(Step {
+            redex: redex.clone(),
+            path: Vec::new(),
+            rule,
+            result: result?,
+        })
+    }
+
+    fn within<F>(mut self, index: usize, rebuild: F) -> Step 
+    where
+        F: FnOnce(NormalExpressionNode) -> NormalExpressionNode
+    {
+        self.result = rebuild(self.result);
+        self.path.push(index);
+        self
+    }
+
+    fn into_contraction(mut self, before: &NormalExpressionNode) -> Contraction {
+        self.path.reverse();
+
+        Contraction {
+            before: before.clone(),
+            redex: self.redex,
+            path: self.path,
+            rule: self.rule,
+            after: self.result,
+        }
+    }
+}
+
+///
+/// Fuel
+/// 
+/// Bounds the work done by reduce: the maximum number of contractions, and the maximum
+/// size (in nodes) the expression may grow to.
+/// 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Fuel {
+    pub steps: usize,
+    pub size: usize,
+}
+
+impl Fuel {
+    // Terms are traversed recursively, so their depth, which their size bounds, must stay
+    // within the stack the session runs on
+    pub const MAX_SIZE: usize = 25_000;
+}
+
+impl Default for Fuel {
+    fn default() -> Fuel {
+        Fuel {
+            steps: 10_000,
+            size: 10_000,
+        }
+    }
+}
+
+///
+/// Fingerprint
+/// 
+/// A fast hasher for the expressions reduce has seen, mixing each word in with a rotate and
+/// a multiply. It makes no attempt to resist collisions, which reduce rules out anyway.
+/// 
+#[derive(Default)]
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+}
+
+impl Hasher for Fingerprint {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, byte: u8) {
+        self.add(byte as u64);
+    }
+
+    fn write_u64(&mut self, word: u64) {
+        self.add(word);
+    }
+
+    fn write_usize(&mut self, word: usize) {
+        self.add(word as u64);
+    }
+}
+
+///
+/// Divergence
+/// 
+/// Reasons for which an expression did not normalise, each holding the partial result.
+/// 
+#[derive(Debug, Clone)]
+pub enum Divergence {
+    // The step budget ran out
+    StepLimit { steps: usize, term: NormalExpressionNode },
+    // The expression grew beyond the size budget
+    SizeLimit { steps: usize, size: usize, term: NormalExpressionNode },
+    // The expression after `steps` contractions is α-equivalent to the one after `earlier`
+    Cycle { steps: usize, earlier: usize, term: NormalExpressionNode },
+}
+
+impl Divergence {
+    pub fn term(&self) -> &NormalExpressionNode {
+        match self {
+            Divergence::StepLimit { term, .. } | 
+            Divergence::SizeLimit { term, .. } | 
+            Divergence::Cycle { term, .. } => term
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::StepLimit { steps, .. } => {
+                write!(f, "did not normalise within {} steps", steps)
+            },
+            Divergence::SizeLimit { steps, size, .. } => {
+                write!(f, "did not normalise: term grew beyond {} nodes after {} steps", size, steps)
+            },
+            Divergence::Cycle { steps, earlier, .. } => {
+                write!(f, "diverges: term after step {} is α-equivalent to the term after step {}", steps, earlier)
+            },
+        }
+    }
 }
 
-pub struct BetaReducer {
-}
+pub struct BetaReducer {
+    strategy: ReductionStrategy,
+    fuel: Fuel,
+    eta: bool,
+    lists: ListEncoding,
+    tracing: bool,
+    contractions: Vec<Contraction>,
+}
+
+impl BetaReducer {
+    pub fn new(strategy: ReductionStrategy) -> BetaReducer {
+        BetaReducer {
+            strategy,
+            fuel: Fuel::default(),
+            eta: false,
+            lists: ListEncoding::Native,
+            tracing: false,
+            contractions: Vec::new(),
+        }
+    }
+
+    pub fn strategy(&self) -> ReductionStrategy {
+        self.strategy
+    }
+
+    pub fn set_fuel(&mut self, fuel: Fuel) {
+        self.fuel = fuel;
+    }
+
+    ///
+    /// Enable η-reduction (λx. f x to f, where x is not free in f). Like β-reduction under
+    /// abstractions, it is only performed by the strategies that reduce to full normal form.
+    /// 
+    pub fn set_eta(&mut self, eta: bool) {
+        self.eta = eta;
+    }
+
+    ///
+    /// The encoding lists were desugared into. length stays built in, so that it still takes
+    /// strings, and takes an abstraction to be a list of this encoding.
+    /// 
+    pub fn set_lists(&mut self, lists: ListEncoding) {
+        self.lists = lists;
+    }
+
+    pub fn set_tracing(&mut self, tracing: bool) {
+        self.tracing = tracing;
+    }
+
+    pub fn trace(&self) -> &[Contraction] {
+        &self.contractions
+    }
+
+    ///
+    /// Capture-avoiding substitution of argument for the free occurrences of variable. A
+    /// binder that would capture a free variable of the argument is renamed first, so the
+    /// result is correct however the names in the expression came about.
+    /// 
+    fn substitute(&self, expression: &NormalExpressionNode, variable: &str, argument: &NormalExpressionNode) -> NormalExpressionNode {
+        self.substitute_free(expression, variable, argument, &argument.free_variables())
+    }
+
+    fn substitute_free(&self, expression: &NormalExpressionNode, variable: &str, argument: &NormalExpressionNode, free: &HashSet<String>) -> NormalExpressionNode {
+        match expression {
+            NormalExpressionNode::Constant(_) => {
+                expression.clone()
+            },
+            NormalExpressionNode::Variable(name) => {
+                if name == variable {
+                    argument.clone()
+                } else {
+                    expression.clone()
+                }
+            },
+            NormalExpressionNode::Abstraction(name, body, curried) => {
+                // No substitution: another variable with the same name is bound in this abstraction
+                if name == variable {
+                    return expression.clone();
+                }
+
+                let (name, body) = self.avoid_capture(name, body, variable, free);
+
+                NormalExpressionNode::Abstraction(name, Rc::new(self.substitute_free(body.as_ref(), variable, argument, free)), *curried)
+            },
+            NormalExpressionNode::Application(function, application) => {
+                NormalExpressionNode::Application(
+                    Rc::new(self.substitute_free(function.as_ref(), variable, argument, free)),
+                    Rc::new(self.substitute_free(application.as_ref(), variable, argument, free)))
+            },
+            NormalExpressionNode::Arithmetic(left, operator, right) => {
+                NormalExpressionNode::Arithmetic(
+                    Rc::new(self.substitute_free(left.as_ref(), variable, argument, free)),
+                    operator.clone(),
+                    Rc::new(self.substitute_free(right.as_ref(), variable, argument, free)))
+            },
+            NormalExpressionNode::Let(name, expression, scope, function) => {
+                // The bound expression is outside the scope of the binding, so it is always 
+                // substituted; the scope is left alone when the let shadows the variable
+                let expression = Rc::new(self.substitute_free(expression.as_ref(), variable, argument, free));
+
+                if name == variable {
+                    return NormalExpressionNode::Let(name.clone(), expression, scope.clone(), *function);
+                }
+
+                let (name, scope) = self.avoid_capture(name, scope, variable, free);
+
+                NormalExpressionNode::Let(name, expression, Rc::new(self.substitute_free(scope.as_ref(), variable, argument, free)), *function)
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                // Every binding is in scope of the bound expressions as well as the body
+                if bindings.iter().any(|(name, _, _)| name == variable) {
+                    return expression.clone();
+                }
+
+                let (bindings, body) = match expression.has_free(variable) {
+                    true => self.rename_bindings_apart(bindings, body, free),
+                    false => (bindings.clone(), body.clone()),
+                };
+
+                NormalExpressionNode::LetRec(
+                    bindings.iter()
+                        .map(|(name, expression, function)| (name.clone(), Rc::new(self.substitute_free(expression.as_ref(), variable, argument, free)), *function))
+                        .collect(),
+                    Rc::new(self.substitute_free(body.as_ref(), variable, argument, free)))
+            },
+            NormalExpressionNode::LetTuple(names, expression, scope) => {
+                let expression = Rc::new(self.substitute_free(expression.as_ref(), variable, argument, free));
+
+                if names.iter().any(|name| name == variable) {
+                    return NormalExpressionNode::LetTuple(names.clone(), expression, scope.clone());
+                }
+
+                let (names, scope) = match scope.has_free(variable) {
+                    true => self.rename_pattern_apart(names, scope, free),
+                    false => (names.clone(), scope.clone()),
+                };
+
+                NormalExpressionNode::LetTuple(names, expression, Rc::new(self.substitute_free(scope.as_ref(), variable, argument, free)))
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                NormalExpressionNode::Conditional(
+                    Rc::new(self.substitute_free(condition.as_ref(), variable, argument, free)),
+                    Rc::new(self.substitute_free(consequent.as_ref(), variable, argument, free)),
+                    Rc::new(self.substitute_free(alternative.as_ref(), variable, argument, free)))
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                NormalExpressionNode::Tuple(elements.iter()
+                    .map(|element| Rc::new(self.substitute_free(element.as_ref(), variable, argument, free)))
+                    .collect())
+            },
+            NormalExpressionNode::Projection(tuple, index) => {
+                NormalExpressionNode::Projection(Rc::new(self.substitute_free(tuple.as_ref(), variable, argument, free)), *index)
+            },
+            NormalExpressionNode::Cons(head, tail) => {
+                NormalExpressionNode::Cons(
+                    Rc::new(self.substitute_free(head.as_ref(), variable, argument, free)),
+                    Rc::new(self.substitute_free(tail.as_ref(), variable, argument, free)))
+            },
+        }
+    }
+
+    // Rename a binder if it would capture one of the free variables of the argument being
+    // substituted for variable in its scope
+    fn avoid_capture(&self, name: &String, scope: &Rc<NormalExpressionNode>, variable: &str, free: &HashSet<String>) -> (String, Rc<NormalExpressionNode>) {
+        // Nothing will be substituted in this scope, so nothing can be captured
+        if !free.contains(name) || !scope.has_free(variable) {
+            return (name.clone(), scope.clone());
+        }
+
+        self.rename_apart(name, scope, free)
+    }
+
+    ///
+    /// Rename a binder, throughout its scope, if its name is among the given variables. The
+    /// new name is the old one primed until it clashes with nothing free in the scope or
+    /// among the variables.
+    /// 
+    fn rename_apart(&self, name: &String, scope: &Rc<NormalExpressionNode>, variables: &HashSet<String>) -> (String, Rc<NormalExpressionNode>) {
+        if !variables.contains(name) {
+            return (name.clone(), scope.clone());
+        }
+
+        let mut taken = scope.free_variables();
+        taken.extend(variables.iter().cloned());
+
+        let mut fresh = format!("{}'", name);
+        while taken.contains(&fresh) {
+            fresh.push('\'');
+        }
+
+        let scope = self.substitute(scope.as_ref(), name, &NormalExpressionNode::Variable(fresh.clone()));
+
+        (fresh, Rc::new(scope))
+    }
+
+    ///
+    /// As rename_apart, for the binders of a recursive let, which are in scope of one 
+    /// another and of the body
+    /// 
+    fn rename_bindings_apart(&self, bindings: &[Binding], body: &Rc<NormalExpressionNode>, variables: &HashSet<String>) -> (Vec<Binding>, Rc<NormalExpressionNode>) {
+        let mut bindings = bindings.to_vec();
+        let mut body = body.clone();
+
+        for index in 0..bindings.len() {
+            let name = bindings[index].0.clone();
+
+            if !variables.contains(&name) {
+                continue;
+            }
+
+            let mut taken = NormalExpressionNode::LetRec(bindings.clone(), body.clone()).free_variables();
+            taken.extend(variables.iter().cloned());
+            taken.extend(bindings.iter().map(|(name, _, _)| name.clone()));
+
+            let mut fresh = format!("{}'", name);
+            while taken.contains(&fresh) {
+                fresh.push('\'');
+            }
+
+            let renamed = NormalExpressionNode::Variable(fresh.clone());
+
+            for binding in bindings.iter_mut() {
+                binding.1 = Rc::new(self.substitute(binding.1.as_ref(), &name, &renamed));
+            }
+            bindings[index].0 = fresh;
+            body = Rc::new(self.substitute(body.as_ref(), &name, &renamed));
+        }
+
+        (bindings, body)
+    }
+
+    ///
+    /// As rename_apart, for the variables of a tuple pattern, which are in scope together
+    /// 
+    fn rename_pattern_apart(&self, names: &[String], scope: &Rc<NormalExpressionNode>, variables: &HashSet<String>) -> (Vec<String>, Rc<NormalExpressionNode>) {
+        let mut names = names.to_vec();
+        let mut scope = scope.clone();
+
+        for index in 0..names.len() {
+            let name = names[index].clone();
+
+            if !variables.contains(&name) {
+                continue;
+            }
+
+            let mut taken = scope.free_variables();
+            taken.extend(variables.iter().cloned());
+            taken.extend(names.iter().cloned());
+
+            let mut fresh = format!("{}'", name);
+            while taken.contains(&fresh) {
+                fresh.push('\'');
+            }
+
+            scope = Rc::new(self.substitute(scope.as_ref(), &name, &NormalExpressionNode::Variable(fresh.clone())));
+            names[index] = fresh;
+        }
+
+        (names, scope)
+    }
+
+    ///
+    /// Apply a primitive to as many arguments as it takes (δ-rule). fix g is g (fix g), which
+    /// unfolding straight away would repeat forever under the strategies that reduce arguments
+    /// or under abstractions, so it becomes the recursive binding let rec f = g f in f instead,
+    /// which unfolds only on demand. The variable is named after the parameter of g. fst t
+    /// and snd t are the projections t.0 and t.1, and cons h t is the list h :: t. 
+    /// 
+    /// Recursive bindings around an argument the primitive needs are lifted over the 
+    /// application first (rule Let).
+    /// 
+    fn apply_primitive(&self, node: &NormalExpressionNode, primitive: Primitive, arguments: &[&Rc<NormalExpressionNode>]) -> Stepped {
+        let lifted = arguments.iter()
+            .map(|argument| argument.as_ref())
+            .enumerate()
+            .find(|(index, argument)| primitive.is_strict(*index) && matches!(argument, NormalExpressionNode::LetRec(_, _)));
+
+        if let Some((index, NormalExpressionNode::LetRec(bindings, body))) = lifted {
+            let free: HashSet<String> = arguments.iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .flat_map(|(_, argument)| argument.free_variables())
+                .collect();
+            let (bindings, body) = self.rename_bindings_apart(bindings, body, &free);
+            let application = self.map_argument(node, arguments.len() - 1 - index, |_| body.as_ref().clone());
+
+            return Ok(Step::contract(ReductionRule::Let, node, Some(NormalExpressionNode::LetRec(bindings, Rc::new(application)))));
+        }
+
+        let result = match (primitive, arguments) {
+            (Primitive::Fix, [argument]) => {
+                let mut name = match argument.as_ref() {
+                    NormalExpressionNode::Abstraction(parameter, _, _) => parameter.clone(),
+                    _ => String::from("x"),
+                };
+
+                let free = argument.free_variables();
+                while free.contains(&name) {
+                    name.push('\'');
+                }
+
+                let variable = Rc::new(NormalExpressionNode::Variable(name.clone()));
+
+                NormalExpressionNode::LetRec(
+                    vec![(name, Rc::new(NormalExpressionNode::Application((*argument).clone(), variable.clone())), false)],
+                    variable)
+            },
+            (Primitive::Fst, [argument]) => NormalExpressionNode::Projection((*argument).clone(), 0),
+            (Primitive::Snd, [argument]) => NormalExpressionNode::Projection((*argument).clone(), 1),
+            (Primitive::Cons, [head, tail]) => NormalExpressionNode::Cons((*head).clone(), (*tail).clone()),
+            (Primitive::Concat | Primitive::CharAt | Primitive::Show, _) => {
+                self.apply_string_primitive(primitive, arguments)?
+            },
+            (Primitive::Length, [argument]) if matches!(argument.as_ref(), NormalExpressionNode::Constant(Constant::String(_))) => {
+                self.apply_string_primitive(primitive, arguments)?
+            },
+            (Primitive::Length, [argument]) if self.is_encoded_list(argument.as_ref()) => {
+                self.lists.length(argument)
+            },
+            _ => return self.apply_list_primitive(node, primitive, arguments),
+        };
+
+        Ok(Step::contract(ReductionRule::Delta, node, Some(result)))
+    }
+
+    ///
+    /// Apply a primitive on strings, once its arguments are constants. Strings are indexed
+    /// by character, from 0.
+    /// 
+    fn apply_string_primitive(&self, primitive: Primitive, arguments: &[&Rc<NormalExpressionNode>]) -> Result<NormalExpressionNode, AlabastaError> {
+        let constants: Vec<&Constant> = arguments.iter()
+            .filter_map(|argument| match argument.as_ref() {
+                NormalExpressionNode::Constant(value) => Some(value),
+                _ => None,
+            })
+            .collect();
+
+        let value = match (primitive, constants.as_slice()) {
+            (Primitive::Concat, [Constant::String(lhs), Constant::String(rhs)]) => Constant::String(format!("{}{}", lhs, rhs)),
+            (Primitive::CharAt, [Constant::String(text), Constant::Integer(index)]) => {
+                let length = text.chars().count();
+
+                match usize::try_from(*index).ok().and_then(|index| text.chars().nth(index)) {
+                    Some(chr) => Constant::Character(chr),
+                    None => {
+                        return Err(AlabastaError::Type(format!("index {} is out of range for a string of length {}", index, length)));
+                    }
+                }
+            },
+            (Primitive::Length, [Constant::String(text)]) => Constant::Integer(text.chars().count() as i64),
+            (Primitive::Show, [Constant::Integer(value)]) => Constant::String(value.to_string()),
+            (Primitive::Show, [Constant::Boolean(value)]) => Constant::String(value.to_string()),
+            _ => {
+                return Err(self.expects(primitive, arguments));
+            }
+        };
+
+        Ok(NormalExpressionNode::Constant(value))
+    }
+
+    ///
+    /// Apply a primitive that takes a list, once the list is the empty list or a cons. map,
+    /// fold and length unfold by one element: map f (h :: t) is f h :: map f t, fold f z 
+    /// (h :: t) is f h (fold f z t) and length (h :: t) is 1 + length t.
+    /// 
+    fn apply_list_primitive(&self, node: &NormalExpressionNode, primitive: Primitive, all: &[&Rc<NormalExpressionNode>]) -> Stepped {
+        let Some((list, arguments)) = all.split_last() else {
+            return Ok(None);
+        };
+
+        let result = match (primitive, list.as_ref()) {
+            (Primitive::Head, NormalExpressionNode::Cons(head, _)) => head.as_ref().clone(),
+            (Primitive::Tail, NormalExpressionNode::Cons(_, tail)) => tail.as_ref().clone(),
+            (Primitive::IsNil, NormalExpressionNode::Cons(_, _)) => NormalExpressionNode::Constant(Constant::Boolean(false)),
+            (Primitive::IsNil, NormalExpressionNode::Constant(Constant::Nil)) => NormalExpressionNode::Constant(Constant::Boolean(true)),
+            (Primitive::Map, NormalExpressionNode::Constant(Constant::Nil)) => NormalExpressionNode::Constant(Constant::Nil),
+            (Primitive::Map, NormalExpressionNode::Cons(head, tail)) => {
+                NormalExpressionNode::Cons(
+                    Rc::new(NormalExpressionNode::Application(arguments[0].clone(), head.clone())),
+                    Rc::new(self.applied(primitive, arguments, tail.clone())))
+            },
+            (Primitive::Fold, NormalExpressionNode::Constant(Constant::Nil)) => arguments[1].as_ref().clone(),
+            (Primitive::Fold, NormalExpressionNode::Cons(head, tail)) => {
+                NormalExpressionNode::Application(
+                    Rc::new(NormalExpressionNode::Application(arguments[0].clone(), head.clone())),
+                    Rc::new(self.applied(primitive, arguments, tail.clone())))
+            },
+            (Primitive::Length, NormalExpressionNode::Constant(Constant::Nil)) => NormalExpressionNode::Constant(Constant::Integer(0)),
+            (Primitive::Length, NormalExpressionNode::Cons(_, tail)) => {
+                NormalExpressionNode::Arithmetic(
+                    Rc::new(NormalExpressionNode::Constant(Constant::Integer(1))),
+                    String::from("+"),
+                    Rc::new(self.applied(primitive, arguments, tail.clone())))
+            },
+            (Primitive::Head | Primitive::Tail, NormalExpressionNode::Constant(Constant::Nil)) => {
+                return Err(AlabastaError::Type(format!("`{}` of the empty list", primitive.name())));
+            },
+            (_, NormalExpressionNode::Constant(_)) => {
+                return Err(self.expects(primitive, all));
+            },
+            _ => {
+                return Ok(None);
+            }
+        };
+
+        Ok(Step::contract(ReductionRule::Delta, node, Some(result)))
+    }
+
+    // A primitive applied to the given arguments followed by one more
+    fn applied(&self, primitive: Primitive, arguments: &[&Rc<NormalExpressionNode>], last: Rc<NormalExpressionNode>) -> NormalExpressionNode {
+        let function = arguments.iter()
+            .fold(NormalExpressionNode::Constant(Constant::Primitive(primitive)), |function, argument| {
+                NormalExpressionNode::Application(Rc::new(function), (*argument).clone())
+            });
+
+        NormalExpressionNode::Application(Rc::new(function), last)
+    }
+
+    // An application of a primitive with one of its arguments, the given number of 
+    // applications down its spine, rebuilt
+    fn map_argument<F>(&self, node: &NormalExpressionNode, depth: usize, rebuild: F) -> NormalExpressionNode
+    where
+        F: FnOnce(&NormalExpressionNode) -> NormalExpressionNode
+    {
+        match (node, depth) {
+            (NormalExpressionNode::Application(function, argument), 0) => {
+                NormalExpressionNode::Application(function.clone(), Rc::new(rebuild(argument.as_ref())))
+            },
+            (NormalExpressionNode::Application(function, argument), _) => {
+                NormalExpressionNode::Application(Rc::new(self.map_argument(function.as_ref(), depth - 1, rebuild)), argument.clone())
+            },
+            _ => node.clone()
+        }
+    }
+
+    // The first argument a primitive needs that is not yet a constant or a cons, or bindings
+    // around one, so that the primitive cannot apply. length also takes an encoded list.
+    fn pending(&self, primitive: Primitive, arguments: &[&Rc<NormalExpressionNode>]) -> Option<usize> {
+        arguments.iter().enumerate()
+            .position(|(index, argument)| primitive.is_strict(index) && !matches!(argument.as_ref(), 
+                NormalExpressionNode::Constant(_) | 
+                NormalExpressionNode::Cons(_, _) | 
+                NormalExpressionNode::LetRec(_, _)))
+            .filter(|index| primitive != Primitive::Length || !self.is_encoded_list(arguments[*index].as_ref()))
+    }
+
+    // Whether an argument of length is a list of the encoding lists were desugared into
+    fn is_encoded_list(&self, node: &NormalExpressionNode) -> bool {
+        self.lists != ListEncoding::Native && matches!(node, NormalExpressionNode::Abstraction(_, _, _))
+    }
+
+    // A primitive applied to values of the wrong kind
+    fn expects(&self, primitive: Primitive, arguments: &[&Rc<NormalExpressionNode>]) -> AlabastaError {
+        let found: Vec<&str> = arguments.iter().enumerate()
+            .filter(|(index, _)| primitive.is_strict(*index))
+            .filter_map(|(_, argument)| match argument.as_ref() {
+                NormalExpressionNode::Constant(value) => Some(value.kind()),
+                argument => self.kind(argument),
+            })
+            .collect();
+
+        AlabastaError::Type(format!("`{}` expects {}, found {}", primitive.name(), self.expected_arguments(primitive), found.join(" and ")))
+    }
+
+    fn expected_arguments(&self, primitive: Primitive) -> &'static str {
+        match primitive {
+            Primitive::Length => "a list or a string",
+            Primitive::Concat => "two strings",
+            Primitive::CharAt => "a string and an integer",
+            Primitive::Show => "an integer or a boolean",
+            _ => "a list",
+        }
+    }
+
+    ///
+    /// The primitive at the head of an application and its arguments, when it is applied
+    /// to as many arguments as it takes
+    /// 
+    fn saturated<'a>(&self, node: &'a NormalExpressionNode) -> Option<(Primitive, Vec<&'a Rc<NormalExpressionNode>>)> {
+        let mut arguments = Vec::new();
+        let mut function = node;
+
+        while let NormalExpressionNode::Application(inner, argument) = function {
+            arguments.push(argument);
+            function = inner.as_ref();
+        }
+
+        let NormalExpressionNode::Constant(Constant::Primitive(primitive)) = function else {
+            return None;
+        };
+
+        arguments.reverse();
+
+        (arguments.len() == primitive.arity()).then_some((*primitive, arguments))
+    }
+
+    ///
+    /// Apply a binary operator to two constants (δ-rule). Arithmetic takes integers, 
+    /// ordering two integers, two characters or two strings, and equality two constants of
+    /// the same kind other than functions and the empty list.
+    /// 
+    fn apply_operator(&self, lhs: &Constant, operator: &str, rhs: &Constant) -> Result<NormalExpressionNode, AlabastaError> {
+        let value = match (lhs, operator, rhs) {
+            (Constant::Integer(lhs), "+" | "-" | "*" | "/" | "%", Constant::Integer(rhs)) => {
+                Constant::Integer(self.apply_arithmetic(*lhs, operator, *rhs)?)
+            },
+            (lhs, "<", rhs) if self.is_ordered(lhs, rhs) => Constant::Boolean(lhs < rhs),
+            (lhs, "<=", rhs) if self.is_ordered(lhs, rhs) => Constant::Boolean(lhs <= rhs),
+            (lhs, ">", rhs) if self.is_ordered(lhs, rhs) => Constant::Boolean(lhs > rhs),
+            (lhs, ">=", rhs) if self.is_ordered(lhs, rhs) => Constant::Boolean(lhs >= rhs),
+            (lhs, "==", rhs) if self.is_comparable(lhs, rhs) => Constant::Boolean(lhs == rhs),
+            (lhs, "!=", rhs) if self.is_comparable(lhs, rhs) => Constant::Boolean(lhs != rhs),
+            (lhs, _, rhs) => {
+                return Err(AlabastaError::Type(format!("`{}` expects {}, found {} and {}", 
+                    operator, self.expected_operands(operator), lhs.kind(), rhs.kind())));
+            }
+        };
+
+        Ok(NormalExpressionNode::Constant(value))
+    }
+
+    fn apply_arithmetic(&self, lhs: i64, operator: &str, rhs: i64) -> Result<i64, AlabastaError> {
+        let value = match operator {
+            "+" => lhs.checked_add(rhs),
+            "-" => lhs.checked_sub(rhs),
+            "*" => lhs.checked_mul(rhs),
+            "/" => lhs.checked_div(rhs),
+            "%" => lhs.checked_rem(rhs),
+            _ => { 
+                return Err(AlabastaError::Type(format!("unknown operator `{}`", operator)));
+            }
+        };
+
+        match value {
+            Some(value) => Ok(value),
+            None if rhs == 0 => {
+                Err(AlabastaError::Arithmetic(ArithmeticError::DivisionByZero { lhs, operator: operator.to_string() }))
+            },
+            None => {
+                Err(AlabastaError::Arithmetic(ArithmeticError::Overflow { lhs, operator: operator.to_string(), rhs }))
+            }
+        }
+    }
+
+    ///
+    /// Apply && or || once its left operand is known (δ-rule): the right operand is the
+    /// result unless the left one decides it
+    /// 
+    fn apply_logical(&self, lhs: &Constant, operator: &str, rhs: &Rc<NormalExpressionNode>) -> Result<NormalExpressionNode, AlabastaError> {
+        match (lhs, operator) {
+            (Constant::Boolean(true), "&&") | (Constant::Boolean(false), "||") => Ok(rhs.as_ref().clone()),
+            (Constant::Boolean(value), _) => Ok(NormalExpressionNode::Constant(Constant::Boolean(*value))),
+            (lhs, _) => Err(AlabastaError::Type(format!("`{}` expects {}, found {}", operator, self.expected_operands(operator), lhs.kind()))),
+        }
+    }
+
+    // Equality is defined on integers, booleans, characters and strings, not on functions or lists
+    fn is_comparable(&self, lhs: &Constant, rhs: &Constant) -> bool {
+        lhs.kind() == rhs.kind() && !matches!(lhs, Constant::Primitive(_) | Constant::Nil)
+    }
+
+    // Ordering is defined on integers, on characters by code point and on strings lexicographically
+    fn is_ordered(&self, lhs: &Constant, rhs: &Constant) -> bool {
+        lhs.kind() == rhs.kind() && matches!(lhs, Constant::Integer(_) | Constant::Character(_) | Constant::String(_))
+    }
+
+    // && and || short-circuit, needing only their left operand
+    fn is_logical(&self, operator: &str) -> bool {
+        matches!(operator, "&&" | "||")
+    }
+
+    // What a value that is not a constant is, for type errors
+    fn kind(&self, node: &NormalExpressionNode) -> Option<&'static str> {
+        match node {
+            NormalExpressionNode::Abstraction(_, _, _) => Some("an abstraction"),
+            NormalExpressionNode::Tuple(_) => Some("a tuple"),
+            NormalExpressionNode::Cons(_, _) => Some("a list"),
+            _ => None
+        }
+    }
+
+    fn expected_operands(&self, operator: &str) -> &'static str {
+        match operator {
+            "&&" | "||" => "boolean operands",
+            "==" | "!=" => "two integers, booleans, characters or strings",
+            "<" | "<=" | ">" | ">=" => "two integers, characters or strings",
+            _ => "integer operands",
+        }
+    }
+
+    ///
+    /// Binary operators are strict under every strategy: operands are reduced left to right
+    /// until both are constants, at which point the operator is applied. && and || are the
+    /// exception, applied as soon as their left operand is a constant, and reduce their right
+    /// operand beforehand only when the strategy reduces to full normal form.
+    /// 
+    fn step_arithmetic<F>(&self, node: &NormalExpressionNode, step: F) -> Stepped
+    where
+        F: Fn(&NormalExpressionNode) -> Stepped
+    {
+        let NormalExpressionNode::Arithmetic(lhs, operator, rhs) = node else {
+            return Ok(None);
+        };
+
+        if self.is_redex(node) {
+            return self.contract_redex(node);
+        }
+
+        if let Some(step) = step(lhs.as_ref())? {
+            return Ok(Some(step.within(0, |lhs| NormalExpressionNode::Arithmetic(Rc::new(lhs), operator.clone(), rhs.clone()))));
+        }
+
+        if !self.is_logical(operator) || self.strategy.is_full() {
+            if let Some(step) = step(rhs.as_ref())? {
+                return Ok(Some(step.within(1, |rhs| NormalExpressionNode::Arithmetic(lhs.clone(), operator.clone(), Rc::new(rhs)))));
+            }
+        }
+
+        // Neither operand can be reduced further; a free variable may yet stand for a 
+        // constant, but an abstraction or a tuple never will
+        if let Some(kind) = [lhs, rhs].iter().find_map(|operand| self.kind(operand.as_ref())) {
+            return Err(AlabastaError::Type(format!("`{}` expects {}, found {}", operator, self.expected_operands(operator), kind)));
+        }
+
+        Ok(None)
+    }
+
+    ///
+    /// Conditionals are lazy under every strategy: the condition is reduced to a constant,
+    /// and only then is the branch it chooses taken. Should the condition be stuck, full
+    /// strategies go on to reduce both branches.
+    /// 
+    fn step_conditional<F>(&self, node: &NormalExpressionNode, step: F) -> Stepped
+    where
+        F: Fn(&NormalExpressionNode) -> Stepped
+    {
+        let NormalExpressionNode::Conditional(condition, consequent, alternative) = node else {
+            return Ok(None);
+        };
+
+        if self.is_redex(node) {
+            return self.contract_redex(node);
+        }
+
+        if let Some(step) = step(condition.as_ref())? {
+            return Ok(Some(step.within(0, |condition| NormalExpressionNode::Conditional(Rc::new(condition), consequent.clone(), alternative.clone()))));
+        }
+
+        if let Some(kind) = self.kind(condition.as_ref()) {
+            return Err(AlabastaError::Type(format!("`if` expects a boolean condition, found {}", kind)));
+        }
+
+        if self.strategy.is_full() {
+            if let Some(step) = step(consequent.as_ref())? {
+                return Ok(Some(step.within(1, |consequent| NormalExpressionNode::Conditional(condition.clone(), Rc::new(consequent), alternative.clone()))));
+            }
+
+            if let Some(step) = step(alternative.as_ref())? {
+                return Ok(Some(step.within(2, |alternative| NormalExpressionNode::Conditional(condition.clone(), consequent.clone(), Rc::new(alternative)))));
+            }
+        }
+
+        Ok(None)
+    }
+
+    ///
+    /// A recursive binding unfolds only when the body demands its value, and is released 
+    /// once the body no longer refers to any binding. Otherwise the body is reduced and, 
+    /// by full strategies, a binding the body demands under an abstraction or in an argument
+    /// is then unfolded there (see unfold_within). Last, full strategies reduce the bound
+    /// expressions, in which nothing is ever unfolded.
+    /// 
+    fn step_let_rec<F>(&self, node: &NormalExpressionNode, step: F) -> Stepped
+    where
+        F: Fn(&NormalExpressionNode) -> Stepped
+    {
+        let NormalExpressionNode::LetRec(bindings, body) = node else {
+            return Ok(None);
+        };
+
+        if self.is_redex(node) {
+            return self.contract_redex(node);
+        }
+
+        if let Some(step) = step(body.as_ref())? {
+            return Ok(Some(step.within(bindings.len(), |body| NormalExpressionNode::LetRec(bindings.clone(), Rc::new(body)))));
+        }
+
+        if self.strategy.is_full() {
+            let free: HashSet<String> = bindings.iter()
+                .flat_map(|(_, expression, _)| expression.free_variables())
+                .collect();
+
+            if let Some(body) = self.unfold_within(bindings, &free, body.as_ref()) {
+                return Ok(Step::contract(ReductionRule::Recursion, node, Some(NormalExpressionNode::LetRec(bindings.clone(), Rc::new(body)))));
+            }
+
+            for (index, (name, expression, function)) in bindings.iter().enumerate() {
+                if let Some(step) = step(expression.as_ref())? {
+                    return Ok(Some(step.within(index, |expression| {
+                        let mut bindings = bindings.clone();
+                        bindings[index] = (name.clone(), Rc::new(expression), *function);
+
+                        NormalExpressionNode::LetRec(bindings, body.clone())
+                    })));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    ///
+    /// A primitive given as many arguments as it takes applies at once, unless it needs the
+    /// values of some of them: the list a list primitive takes, or the strings and integers
+    /// a string primitive takes. These are then reduced left to right, as far as a constant
+    /// or a cons, and by strict strategies completely, before the primitive applies.
+    /// 
+    fn step_primitive<F>(&self, node: &NormalExpressionNode, step: F) -> Stepped
+    where
+        F: Fn(&NormalExpressionNode) -> Stepped
+    {
+        let Some((primitive, arguments)) = self.saturated(node) else {
+            return Ok(None);
+        };
+
+        let pending = self.pending(primitive, &arguments);
+
+        for (index, argument) in arguments.iter().enumerate() {
+            if !primitive.is_strict(index) || !(self.strategy.is_strict() || pending == Some(index)) {
+                continue;
+            }
+
+            if let Some(step) = step(argument.as_ref())? {
+                return Ok(Some(self.step_argument(node, arguments.len() - 1 - index, step)));
+            }
+
+            // An argument that is stuck keeps the primitive from applying
+            if pending == Some(index) {
+                return match self.kind(argument.as_ref()) {
+                    Some(_) => Err(self.expects(primitive, &arguments)),
+                    None => Ok(None)
+                };
+            }
+        }
+
+        self.contract_redex(node)
+    }
+
+    // A step within an argument of an application, the given number of applications down its spine
+    fn step_argument(&self, node: &NormalExpressionNode, depth: usize, step: Step) -> Step {
+        match (node, depth) {
+            (NormalExpressionNode::Application(function, _), 0) => {
+                step.within(1, |argument| NormalExpressionNode::Application(function.clone(), Rc::new(argument)))
+            },
+            (NormalExpressionNode::Application(function, argument), _) => {
+                self.step_argument(function.as_ref(), depth - 1, step)
+                    .within(0, |function| NormalExpressionNode::Application(Rc::new(function), argument.clone()))
+            },
+            _ => step
+        }
+    }
+
+    ///
+    /// A projection reduces its tuple until the component can be taken. Tuple patterns are 
+    /// matched likewise, once the tuple is reduced far enough to show its components, each
+    /// of which is then bound by a let of its own; should the tuple be stuck, full 
+    /// strategies go on to reduce the scope.
+    /// 
+    fn step_projection<F>(&self, node: &NormalExpressionNode, step: F) -> Stepped
+    where
+        F: Fn(&NormalExpressionNode) -> Stepped
+    {
+        let NormalExpressionNode::Projection(tuple, index) = node else {
+            return Ok(None);
+        };
+
+        // Strict strategies take a component only once every component has been reduced
+        if !self.strategy.is_strict() && self.is_redex(node) {
+            return self.contract_redex(node);
+        }
+
+        if let Some(step) = step(tuple.as_ref())? {
+            return Ok(Some(step.within(0, |tuple| NormalExpressionNode::Projection(Rc::new(tuple), *index))));
+        }
+
+        if self.is_redex(node) {
+            return self.contract_redex(node);
+        }
+
+        if let Some(kind) = self.kind(tuple.as_ref()) {
+            return Err(AlabastaError::Type(format!("`.{}` expects a tuple, found {}", index, kind)));
+        }
+
+        Ok(None)
+    }
+
+    fn step_let_tuple<F>(&self, node: &NormalExpressionNode, step: F) -> Stepped
+    where
+        F: Fn(&NormalExpressionNode) -> Stepped
+    {
+        let NormalExpressionNode::LetTuple(names, expression, body) = node else {
+            return Ok(None);
+        };
+
+        if self.is_redex(node) {
+            return self.contract_redex(node);
+        }
+
+        if let Some(step) = step(expression.as_ref())? {
+            return Ok(Some(step.within(0, |expression| NormalExpressionNode::LetTuple(names.clone(), Rc::new(expression), body.clone()))));
+        }
+
+        if let Some(kind) = self.kind(expression.as_ref()) {
+            return Err(AlabastaError::Type(format!("a tuple pattern expects a tuple, found {}", kind)));
+        }
+
+        if self.strategy.is_full() {
+            if let Some(step) = step(body.as_ref())? {
+                return Ok(Some(step.within(1, |body| NormalExpressionNode::LetTuple(names.clone(), expression.clone(), Rc::new(body)))));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // The binding whose value the body of a recursive let demands, if any
+    fn demanded_binding<'a>(&self, bindings: &'a [Binding], body: &NormalExpressionNode) -> Option<&'a Binding> {
+        let demanded = self.demand(body)?;
+
+        bindings.iter().find(|(name, _, _)| name == demanded)
+    }
+
+    ///
+    /// Unfold the first binding of a recursive let demanded within its body, outermost
+    /// first: under an abstraction, or in an argument of an application or an operand of an
+    /// operator that is stuck on something else. Binders on the way that would capture a
+    /// variable free in the bound expressions are renamed. The branches of a conditional are
+    /// not searched, so a recursive function whose recursion is guarded by a condition on
+    /// its parameter is not unfolded for ever.
+    /// 
+    fn unfold_within(&self, bindings: &[Binding], free: &HashSet<String>, node: &NormalExpressionNode) -> Option<NormalExpressionNode> {
+        if let Some((name, expression, _)) = self.demanded_binding(bindings, node) {
+            return Some(self.instantiate(node, name, expression.as_ref()));
+        }
+
+        match node {
+            NormalExpressionNode::Abstraction(parameter, body, curried) => {
+                // The bindings are shadowed in this scope
+                if bindings.iter().any(|(name, _, _)| name == parameter) {
+                    return None;
+                }
+
+                let (parameter, body) = self.rename_apart(parameter, body, free);
+                let body = self.unfold_within(bindings, free, body.as_ref())?;
+
+                Some(NormalExpressionNode::Abstraction(parameter, Rc::new(body), *curried))
+            },
+            NormalExpressionNode::Application(function, argument) => {
+                match self.unfold_within(bindings, free, function.as_ref()) {
+                    Some(function) => Some(NormalExpressionNode::Application(Rc::new(function), argument.clone())),
+                    None => {
+                        self.unfold_within(bindings, free, argument.as_ref())
+                            .map(|argument| NormalExpressionNode::Application(function.clone(), Rc::new(argument)))
+                    }
+                }
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                match self.unfold_within(bindings, free, lhs.as_ref()) {
+                    Some(lhs) => Some(NormalExpressionNode::Arithmetic(Rc::new(lhs), operator.clone(), rhs.clone())),
+                    None => {
+                        self.unfold_within(bindings, free, rhs.as_ref())
+                            .map(|rhs| NormalExpressionNode::Arithmetic(lhs.clone(), operator.clone(), Rc::new(rhs)))
+                    }
+                }
+            },
+            _ => None
+        }
+    }
+
+    // Whether the body of a recursive let refers to none of its bindings
+    fn is_unused(&self, bindings: &[Binding], body: &NormalExpressionNode) -> bool {
+        !bindings.iter().any(|(name, _, _)| body.has_free(name))
+    }
+
+    ///
+    /// Replace the occurrence of a variable that demand finds with a value, renaming any
+    /// binder on the way that would capture a variable of the value
+    /// 
+    fn instantiate(&self, node: &NormalExpressionNode, variable: &str, value: &NormalExpressionNode) -> NormalExpressionNode {
+        match node {
+            NormalExpressionNode::Variable(name) if name == variable => {
+                value.clone()
+            },
+            NormalExpressionNode::Application(function, argument) => {
+                let pending = self.saturated(node)
+                    .and_then(|(primitive, arguments)| Some((arguments.len(), self.pending(primitive, &arguments)?)));
+
+                match pending {
+                    Some((count, index)) => {
+                        self.map_argument(node, count - 1 - index, |argument| self.instantiate(argument, variable, value))
+                    },
+                    None => {
+                        NormalExpressionNode::Application(Rc::new(self.instantiate(function.as_ref(), variable, value)), argument.clone())
+                    }
+                }
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                match lhs.as_ref() {
+                    NormalExpressionNode::Constant(_) => {
+                        NormalExpressionNode::Arithmetic(lhs.clone(), operator.clone(), Rc::new(self.instantiate(rhs.as_ref(), variable, value)))
+                    },
+                    _ => {
+                        NormalExpressionNode::Arithmetic(Rc::new(self.instantiate(lhs.as_ref(), variable, value)), operator.clone(), rhs.clone())
+                    }
+                }
+            },
+            NormalExpressionNode::Let(_, _, _, _) => {
+                self.instantiate_let(node, &self.demanded_parameters(node), variable, &value.free_variables(), value)
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                let (bindings, body) = self.rename_bindings_apart(bindings, body, &value.free_variables());
+
+                NormalExpressionNode::LetRec(bindings, Rc::new(self.instantiate(body.as_ref(), variable, value)))
+            },
+            NormalExpressionNode::LetTuple(names, expression, body) => {
+                NormalExpressionNode::LetTuple(names.clone(), Rc::new(self.instantiate(expression.as_ref(), variable, value)), body.clone())
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                NormalExpressionNode::Conditional(Rc::new(self.instantiate(condition.as_ref(), variable, value)), consequent.clone(), alternative.clone())
+            },
+            NormalExpressionNode::Projection(tuple, index) => {
+                NormalExpressionNode::Projection(Rc::new(self.instantiate(tuple.as_ref(), variable, value)), *index)
+            },
+            _ => node.clone()
+        }
+    }
+
+    // As instantiate, for a chain of lets whose demanded parameters are known (see
+    // step_let_by_need)
+    fn instantiate_let(&self, node: &NormalExpressionNode, demanded: &[bool], variable: &str, free: &HashSet<String>, value: &NormalExpressionNode) -> NormalExpressionNode {
+        let NormalExpressionNode::Let(parameter, expression, body, function) = node else {
+            return self.instantiate(node, variable, value);
+        };
+
+        if demanded[0] {
+            return NormalExpressionNode::Let(parameter.clone(), Rc::new(self.instantiate(expression.as_ref(), variable, value)), body.clone(), *function);
+        }
+
+        let (parameter, body) = self.rename_apart(parameter, body, free);
+
+        NormalExpressionNode::Let(parameter, expression.clone(), Rc::new(self.instantiate_let(body.as_ref(), &demanded[1..], variable, free, value)), *function)
+    }
+
+    ///
+    /// Contract the redex at the root of an expression, substituting for β and let
+    /// 
+    fn contract_redex(&self, node: &NormalExpressionNode) -> Stepped {
+        let step = match node {
+            NormalExpressionNode::Application(function, argument) => {
+                if let Some((primitive, arguments)) = self.saturated(node) {
+                    return self.apply_primitive(node, primitive, &arguments);
+                }
+
+                match function.as_ref() {
+                    NormalExpressionNode::Abstraction(parameter, body, _) => {
+                        Step::contract(ReductionRule::Beta, node, Some(self.substitute(body.as_ref(), parameter, argument.as_ref())))
+                    },
+                    // Lift the bindings over the argument, renaming any that would capture its variables
+                    NormalExpressionNode::LetRec(bindings, body) => {
+                        let (bindings, body) = self.rename_bindings_apart(bindings, body, &argument.free_variables());
+
+                        Step::contract(ReductionRule::Let, node, 
+                            Some(NormalExpressionNode::LetRec(bindings, Rc::new(NormalExpressionNode::Application(body, argument.clone())))))
+                    },
+                    _ => None
+                }
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                match (lhs.as_ref(), rhs.as_ref()) {
+                    (NormalExpressionNode::Constant(lhs), _) if self.is_logical(operator) => {
+                        Step::contract(ReductionRule::Delta, node, Some(self.apply_logical(lhs, operator, rhs)?))
+                    },
+                    (NormalExpressionNode::Constant(lhs), NormalExpressionNode::Constant(rhs)) => {
+                        Step::contract(ReductionRule::Delta, node, Some(self.apply_operator(lhs, operator, rhs)?))
+                    },
+                    _ => None
+                }
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                match condition.as_ref() {
+                    NormalExpressionNode::Constant(Constant::Boolean(true)) => {
+                        Step::contract(ReductionRule::Delta, node, Some(consequent.as_ref().clone()))
+                    },
+                    NormalExpressionNode::Constant(Constant::Boolean(false)) => {
+                        Step::contract(ReductionRule::Delta, node, Some(alternative.as_ref().clone()))
+                    },
+                    NormalExpressionNode::Constant(value) => {
+                        return Err(AlabastaError::Type(format!("`if` expects a boolean condition, found {}", value.kind())));
+                    },
+                    _ => None
+                }
+            },
+            NormalExpressionNode::Let(parameter, expression, body, _) => {
+                Step::contract(ReductionRule::Let, node, Some(self.substitute(body.as_ref(), parameter, expression.as_ref())))
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                match self.demanded_binding(bindings, body.as_ref()) {
+                    Some((name, expression, _)) => {
+                        let body = self.instantiate(body.as_ref(), name, expression.as_ref());
+
+                        Step::contract(ReductionRule::Recursion, node, Some(NormalExpressionNode::LetRec(bindings.clone(), Rc::new(body))))
+                    },
+                    None if self.is_unused(bindings, body.as_ref()) => {
+                        Step::contract(ReductionRule::Let, node, Some(body.as_ref().clone()))
+                    },
+                    None => None
+                }
+            },
+            NormalExpressionNode::LetTuple(names, expression, body) => {
+                match expression.as_ref() {
+                    // let (x, y) = (a, b) in e is let x = a in let y = b in e, the variables
+                    // renamed so that b cannot refer to x
+                    NormalExpressionNode::Tuple(elements) if elements.len() == names.len() => {
+                        let variables: HashSet<String> = elements.iter()
+                            .flat_map(|element| element.free_variables())
+                            .collect();
+                        let (names, body) = self.rename_pattern_apart(names, body, &variables);
+
+                        let result = names.into_iter().zip(elements.iter()).rev()
+                            .fold(body, |body, (name, element)| Rc::new(NormalExpressionNode::Let(name, element.clone(), body, false)));
+
+                        Step::contract(ReductionRule::Let, node, Some(result.as_ref().clone()))
+                    },
+                    NormalExpressionNode::Tuple(elements) => {
+                        return Err(AlabastaError::Type(format!("a pattern of {} variables cannot match a tuple of {} components", names.len(), elements.len())));
+                    },
+                    NormalExpressionNode::Constant(value) => {
+                        return Err(AlabastaError::Type(format!("a tuple pattern expects a tuple, found {}", value.kind())));
+                    },
+                    _ => None
+                }
+            },
+            NormalExpressionNode::Projection(tuple, index) => {
+                match tuple.as_ref() {
+                    NormalExpressionNode::Tuple(elements) => {
+                        let Some(element) = elements.get(*index) else {
+                            return Err(AlabastaError::Type(format!("`.{}` is out of range for a tuple of {} components", index, elements.len())));
+                        };
+
+                        Step::contract(ReductionRule::Delta, node, Some(element.as_ref().clone()))
+                    },
+                    NormalExpressionNode::Constant(value) => {
+                        return Err(AlabastaError::Type(format!("`.{}` expects a tuple, found {}", index, value.kind())));
+                    },
+                    // Lift the bindings over the projection
+                    NormalExpressionNode::LetRec(bindings, body) => {
+                        Step::contract(ReductionRule::Let, node, 
+                            Some(NormalExpressionNode::LetRec(bindings.clone(), Rc::new(NormalExpressionNode::Projection(body.clone(), *index)))))
+                    },
+                    _ => None
+                }
+            },
+            NormalExpressionNode::Abstraction(_, body, _) if self.is_eta_redex(node) => {
+                match body.as_ref() {
+                    NormalExpressionNode::Application(function, _) => {
+                        Step::contract(ReductionRule::Eta, node, Some(function.as_ref().clone()))
+                    },
+                    _ => None
+                }
+            },
+            _ => None
+        };
+
+        Ok(step)
+    }
+
+    // λx. f x, where x is not free in f, when η-reduction is enabled
+    fn is_eta_redex(&self, node: &NormalExpressionNode) -> bool {
+        let NormalExpressionNode::Abstraction(parameter, body, _) = node else {
+            return false;
+        };
+
+        let NormalExpressionNode::Application(function, argument) = body.as_ref() else {
+            return false;
+        };
+
+        self.eta
+            && matches!(argument.as_ref(), NormalExpressionNode::Variable(name) if name == parameter)
+            && !function.has_free(parameter)
+    }
+
+    // Whether the root of an expression is a redex, whether or not contracting it succeeds
+    fn is_redex(&self, node: &NormalExpressionNode) -> bool {
+        match node {
+            NormalExpressionNode::Application(function, _) => {
+                // A primitive applies once the arguments it needs are known, or found not to be of the right kind
+                if let Some((primitive, arguments)) = self.saturated(node) {
+                    return self.pending(primitive, &arguments).is_none();
+                }
+
+                matches!(function.as_ref(), NormalExpressionNode::Abstraction(_, _, _) | NormalExpressionNode::LetRec(_, _))
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                match (lhs.as_ref(), rhs.as_ref()) {
+                    (NormalExpressionNode::Constant(_), _) if self.is_logical(operator) => true,
+                    (NormalExpressionNode::Constant(_), NormalExpressionNode::Constant(_)) => true,
+                    _ => false
+                }
+            },
+            NormalExpressionNode::Conditional(condition, _, _) => {
+                matches!(condition.as_ref(), NormalExpressionNode::Constant(_))
+            },
+            NormalExpressionNode::Let(_, _, _, _) => true,
+            NormalExpressionNode::LetTuple(_, expression, _) => {
+                matches!(expression.as_ref(), NormalExpressionNode::Tuple(_) | NormalExpressionNode::Constant(_))
+            },
+            NormalExpressionNode::Projection(tuple, _) => {
+                matches!(tuple.as_ref(), 
+                    NormalExpressionNode::Tuple(_) | 
+                    NormalExpressionNode::Constant(_) | 
+                    NormalExpressionNode::LetRec(_, _))
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                self.demanded_binding(bindings, body.as_ref()).is_some() || self.is_unused(bindings, body.as_ref())
+            },
+            NormalExpressionNode::Abstraction(_, _, _) => self.is_eta_redex(node),
+            _ => false
+        }
+    }
+
+    ///
+    /// Contract the redex at the given path, regardless of the reduction strategy
+    /// 
+    fn contract_within(&self, node: &NormalExpressionNode, path: &[usize]) -> Stepped {
+        let Some((index, path)) = path.split_first() else {
+            return self.contract_redex(node);
+        };
+
+        match (node, index) {
+            (NormalExpressionNode::Abstraction(parameter, body, curried), 0) => {
+                Ok(self.contract_within(body.as_ref(), path)?
+                    .map(|step| step.within(0, |body| NormalExpressionNode::Abstraction(parameter.clone(), Rc::new(body), *curried))))
+            },
+            (NormalExpressionNode::Application(function, argument), 0) => {
+                Ok(self.contract_within(function.as_ref(), path)?
+                    .map(|step| step.within(0, |function| NormalExpressionNode::Application(Rc::new(function), argument.clone()))))
+            },
+            (NormalExpressionNode::Application(function, argument), 1) => {
+                Ok(self.contract_within(argument.as_ref(), path)?
+                    .map(|step| step.within(1, |argument| NormalExpressionNode::Application(function.clone(), Rc::new(argument)))))
+            },
+            (NormalExpressionNode::Arithmetic(lhs, operator, rhs), 0) => {
+                Ok(self.contract_within(lhs.as_ref(), path)?
+                    .map(|step| step.within(0, |lhs| NormalExpressionNode::Arithmetic(Rc::new(lhs), operator.clone(), rhs.clone()))))
+            },
+            (NormalExpressionNode::Arithmetic(lhs, operator, rhs), 1) => {
+                Ok(self.contract_within(rhs.as_ref(), path)?
+                    .map(|step| step.within(1, |rhs| NormalExpressionNode::Arithmetic(lhs.clone(), operator.clone(), Rc::new(rhs)))))
+            },
+            (NormalExpressionNode::Let(parameter, expression, body, function), 0) => {
+                Ok(self.contract_within(expression.as_ref(), path)?
+                    .map(|step| step.within(0, |expression| NormalExpressionNode::Let(parameter.clone(), Rc::new(expression), body.clone(), *function))))
+            },
+            (NormalExpressionNode::Let(parameter, expression, body, function), 1) => {
+                Ok(self.contract_within(body.as_ref(), path)?
+                    .map(|step| step.within(1, |body| NormalExpressionNode::Let(parameter.clone(), expression.clone(), Rc::new(body), *function))))
+            },
+            (NormalExpressionNode::LetRec(bindings, body), index) if *index == bindings.len() => {
+                Ok(self.contract_within(body.as_ref(), path)?
+                    .map(|step| step.within(*index, |body| NormalExpressionNode::LetRec(bindings.clone(), Rc::new(body)))))
+            },
+            (NormalExpressionNode::LetRec(bindings, body), index) if *index < bindings.len() => {
+                Ok(self.contract_within(bindings[*index].1.as_ref(), path)?
+                    .map(|step| step.within(*index, |expression| {
+                        let mut bindings = bindings.clone();
+                        bindings[*index].1 = Rc::new(expression);
+
+                        NormalExpressionNode::LetRec(bindings, body.clone())
+                    })))
+            },
+            (NormalExpressionNode::Conditional(condition, consequent, alternative), 0) => {
+                Ok(self.contract_within(condition.as_ref(), path)?
+                    .map(|step| step.within(0, |condition| NormalExpressionNode::Conditional(Rc::new(condition), consequent.clone(), alternative.clone()))))
+            },
+            (NormalExpressionNode::Conditional(condition, consequent, alternative), 1) => {
+                Ok(self.contract_within(consequent.as_ref(), path)?
+                    .map(|step| step.within(1, |consequent| NormalExpressionNode::Conditional(condition.clone(), Rc::new(consequent), alternative.clone()))))
+            },
+            (NormalExpressionNode::Conditional(condition, consequent, alternative), 2) => {
+                Ok(self.contract_within(alternative.as_ref(), path)?
+                    .map(|step| step.within(2, |alternative| NormalExpressionNode::Conditional(condition.clone(), consequent.clone(), Rc::new(alternative)))))
+            },
+            (NormalExpressionNode::LetTuple(names, expression, body), 0) => {
+                Ok(self.contract_within(expression.as_ref(), path)?
+                    .map(|step| step.within(0, |expression| NormalExpressionNode::LetTuple(names.clone(), Rc::new(expression), body.clone()))))
+            },
+            (NormalExpressionNode::LetTuple(names, expression, body), 1) => {
+                Ok(self.contract_within(body.as_ref(), path)?
+                    .map(|step| step.within(1, |body| NormalExpressionNode::LetTuple(names.clone(), expression.clone(), Rc::new(body)))))
+            },
+            (NormalExpressionNode::Tuple(elements), index) if *index < elements.len() => {
+                Ok(self.contract_within(elements[*index].as_ref(), path)?
+                    .map(|step| step.within(*index, |element| {
+                        let mut elements = elements.clone();
+                        elements[*index] = Rc::new(element);
+
+                        NormalExpressionNode::Tuple(elements)
+                    })))
+            },
+            (NormalExpressionNode::Projection(tuple, index), 0) => {
+                Ok(self.contract_within(tuple.as_ref(), path)?
+                    .map(|step| step.within(0, |tuple| NormalExpressionNode::Projection(Rc::new(tuple), *index))))
+            },
+            (NormalExpressionNode::Cons(head, tail), 0) => {
+                Ok(self.contract_within(head.as_ref(), path)?
+                    .map(|step| step.within(0, |head| NormalExpressionNode::Cons(Rc::new(head), tail.clone()))))
+            },
+            (NormalExpressionNode::Cons(head, tail), 1) => {
+                Ok(self.contract_within(tail.as_ref(), path)?
+                    .map(|step| step.within(1, |tail| NormalExpressionNode::Cons(head.clone(), Rc::new(tail)))))
+            },
+            _ => Ok(None)
+        }
+    }
+
+    fn collect_redexes(&self, node: &NormalExpressionNode, path: &mut Vec<usize>, redexes: &mut Vec<Vec<usize>>) {
+        if self.is_redex(node) {
+            redexes.push(path.clone());
+        }
 
-impl BetaReducer {
-    pub fn new() -> BetaReducer {
-        BetaReducer {
+        let children: Vec<&Rc<NormalExpressionNode>> = match node {
+            NormalExpressionNode::Abstraction(_, body, _) => vec![body],
+            NormalExpressionNode::Application(function, argument) => vec![function, argument],
+            NormalExpressionNode::Arithmetic(lhs, _, rhs) => vec![lhs, rhs],
+            NormalExpressionNode::Let(_, expression, body, _) => vec![expression, body],
+            NormalExpressionNode::LetRec(bindings, body) => {
+                bindings.iter().map(|(_, expression, _)| expression).chain(std::iter::once(body)).collect()
+            },
+            NormalExpressionNode::LetTuple(_, expression, body) => vec![expression, body],
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => vec![condition, consequent, alternative],
+            NormalExpressionNode::Tuple(elements) => elements.iter().collect(),
+            NormalExpressionNode::Projection(tuple, _) => vec![tuple],
+            NormalExpressionNode::Cons(head, tail) => vec![head, tail],
+            _ => vec![],
+        };
+
+        for (index, child) in children.into_iter().enumerate() {
+            path.push(index);
+            self.collect_redexes(child.as_ref(), path, redexes);
+            path.pop();
         }
     }
 
-    fn substitute(&self, expression: &NormalExpressionNode, variable: &String, argument: &NormalExpressionNode ) -> Option<NormalExpressionNode> {
-        match expression {
-            NormalExpressionNode::Constant(_) => {
-                Some(expression.clone())
+    ///
+    /// Paths of every redex in an expression, leftmost outermost first
+    /// 
+    pub fn redexes(&self, node: &NormalExpressionNode) -> Vec<Vec<usize>> {
+        let mut redexes = Vec::new();
+
+        self.collect_redexes(node, &mut Vec::new(), &mut redexes);
+
+        redexes
+    }
+
+    ///
+    /// Contract a specific redex, identified by its path (see redexes)
+    /// 
+    pub fn contract_at(&self, node: &NormalExpressionNode, path: &[usize]) -> Result<Option<Contraction>, AlabastaError> {
+        Ok(self.contract_within(node, path)?
+            .map(|step| step.into_contraction(node)))
+    }
+
+    ///
+    /// Contract a single redex, chosen according to the reduction strategy. Returns None
+    /// when the expression contains no further redexes for the strategy.
+    /// 
+    pub fn step(&self, node: &NormalExpressionNode) -> Result<Option<NormalExpressionNode>, AlabastaError> {
+        Ok(self.next_contraction(node)?
+            .map(|contraction| contraction.after))
+    }
+
+    ///
+    /// As step, but describing the contraction performed
+    /// 
+    pub fn next_contraction(&self, node: &NormalExpressionNode) -> Result<Option<Contraction>, AlabastaError> {
+        let step = match self.strategy {
+            ReductionStrategy::CallByNeed => self.step_by_need(node),
+            _ => self.step_by_substitution(node),
+        }?;
+
+        Ok(step.map(|step| step.into_contraction(node)))
+    }
+
+    ///
+    /// Normal order, applicative order, call-by-name and call-by-value differ only in 
+    /// whether arguments are reduced before substitution (strict) and whether reduction
+    /// continues under abstractions and into the arguments of stuck applications (full).
+    /// 
+    fn step_by_substitution(&self, node: &NormalExpressionNode) -> Stepped {
+        let strict = self.strategy.is_strict();
+        let full = self.strategy.is_full();
+
+        match node {
+            // Variables and constants are already in normal form
+            NormalExpressionNode::Variable(_) | NormalExpressionNode::Constant(_) => {
+                Ok(None)
             },
-            NormalExpressionNode::Variable(name) => {
-                if name == variable {
-                    Some(argument.clone())
-                } else {
-                    Some(NormalExpressionNode::Variable(name.clone()))
+            // Only full strategies reduce under an abstraction
+            NormalExpressionNode::Abstraction(parameter, body, curried) => {
+                if !full {
+                    return Ok(None);
                 }
-            },
-            NormalExpressionNode::Abstraction(name, body) => {
-                // No substitution: another variable with the same name is bound in this abstraction
-                if name == variable {
-                    Some(NormalExpressionNode::Abstraction(name.clone(), body.clone()))
-                } else {
-                    self.substitute(body.as_ref(), variable, argument)
-                        .map_or(
-                            None, 
-                            |body| Some(NormalExpressionNode::Abstraction(name.clone(), Rc::new(body))))
+
+                // Normal order contracts an η-redex before its body, applicative order after
+                if !strict && self.is_eta_redex(node) {
+                    return self.contract_redex(node);
+                }
+
+                if let Some(step) = self.step_by_substitution(body.as_ref())? {
+                    return Ok(Some(step.within(0, |body| NormalExpressionNode::Abstraction(parameter.clone(), Rc::new(body), *curried))));
                 }
+
+                if self.is_eta_redex(node) {
+                    return self.contract_redex(node);
+                }
+
+                Ok(None)
             },
-            NormalExpressionNode::Application(function, application) => {
-                // Substitute both the function and the application
-                let function = self.substitute(function.as_ref(), variable, argument);
-                let application = self.substitute(application.as_ref(), variable, argument);
+            // Application
+            NormalExpressionNode::Application(function, argument) => {
+                // Lazy strategies substitute the argument as is
+                if !strict {
+                    if let NormalExpressionNode::Abstraction(_, _, _) = function.as_ref() {
+                        return self.contract_redex(node);
+                    }
+                }
 
-                // If either substitution failed, we can't substitute the application
-                let result = match (function, application) {
-                    (Some(function), Some(application)) => {
-                        Some(NormalExpressionNode::Application(Rc::new(function), Rc::new(application)))
-                    },
-                    _ => {
-                        None
+                // Recursive bindings around an abstraction are lifted over the argument before
+                // anything in the abstraction is unfolded (see step_let_rec)
+                if let NormalExpressionNode::LetRec(_, _) = function.as_ref() {
+                    if self.is_answer(function.as_ref()) {
+                        return self.contract_redex(node);
                     }
-                };
+                }
 
-                // println!("Substitution: Application => "); pretty_print_normal(&result.clone().unwrap()); println!();
+                if let Some(step) = self.step_by_substitution(function.as_ref())? {
+                    return Ok(Some(step.within(0, |function| NormalExpressionNode::Application(Rc::new(function), argument.clone()))));
+                }
 
-                result
-            },
-            NormalExpressionNode::Arithmetic(left, operator, right) => {
-                // Substitute both the left and right side of the arithmetic expression
-                let left = self.substitute(left.as_ref(), variable, argument);
-                let right = self.substitute(right.as_ref(), variable, argument);
+                if self.saturated(node).is_some() {
+                    return self.step_primitive(node, |node| self.step_by_substitution(node));
+                }
 
-                // If either substitution failed, we can't substitute the arithmetic expression
-                let result = match (left, right) {
-                    (Some(left), Some(right)) => {
-                        Some(NormalExpressionNode::Arithmetic(Rc::new(left), operator.clone(), Rc::new(right)))
+                match function.as_ref() {
+                    NormalExpressionNode::LetRec(_, _) => {
+                        return self.contract_redex(node);
                     },
-                    _ => {
-                        None
+                    // A primitive awaiting more arguments
+                    NormalExpressionNode::Constant(Constant::Primitive(_)) => { },
+                    NormalExpressionNode::Constant(value) => {
+                        return Err(self.apply_constant(value));
+                    },
+                    NormalExpressionNode::Tuple(_) | NormalExpressionNode::Cons(_, _) => {
+                        return Err(self.apply_value(function.as_ref()));
+                    },
+                    _ => { }
+                }
+
+                if strict || full {
+                    if let Some(step) = self.step_by_substitution(argument.as_ref())? {
+                        return Ok(Some(step.within(1, |argument| NormalExpressionNode::Application(function.clone(), Rc::new(argument)))));
                     }
-                };
+                }
+
+                // Strict strategies substitute only once the argument has been reduced
+                match function.as_ref() {
+                    NormalExpressionNode::Abstraction(_, _, _) if strict => {
+                        self.contract_redex(node)
+                    },
+                    _ => Ok(None)
+                }
+            },
+            // Arithmetic
+            NormalExpressionNode::Arithmetic(_, _, _) => {
+                self.step_arithmetic(node, |node| self.step_by_substitution(node))
+            },
+            NormalExpressionNode::Conditional(_, _, _) => {
+                self.step_conditional(node, |node| self.step_by_substitution(node))
+            },
+            NormalExpressionNode::LetRec(_, _) => {
+                self.step_let_rec(node, |node| self.step_by_substitution(node))
+            },
+            NormalExpressionNode::LetTuple(_, _, _) => {
+                self.step_let_tuple(node, |node| self.step_by_substitution(node))
+            },
+            NormalExpressionNode::Projection(_, _) => {
+                self.step_projection(node, |node| self.step_by_substitution(node))
+            },
+            // Tuples and lists are constructed lazily, their components reduced left to right
+            // only by the strategies that reduce arguments
+            NormalExpressionNode::Cons(head, tail) => {
+                if !(strict || full) {
+                    return Ok(None);
+                }
 
-                // println!("Substitution: Arithmetic => "); pretty_print_normal(&result.clone().unwrap()); println!();
+                if let Some(step) = self.step_by_substitution(head.as_ref())? {
+                    return Ok(Some(step.within(0, |head| NormalExpressionNode::Cons(Rc::new(head), tail.clone()))));
+                }
 
-                result
+                Ok(self.step_by_substitution(tail.as_ref())?
+                    .map(|step| step.within(1, |tail| NormalExpressionNode::Cons(head.clone(), Rc::new(tail)))))
             },
-            _ => {
-                // Technically, we shouldn't be here
-                // println!("Substitution: Default");
-                Some(NormalExpressionNode::Application(Rc::new(expression.clone()), Rc::new(argument.clone())))
+            NormalExpressionNode::Tuple(elements) => {
+                if !(strict || full) {
+                    return Ok(None);
+                }
+
+                for (index, element) in elements.iter().enumerate() {
+                    if let Some(step) = self.step_by_substitution(element.as_ref())? {
+                        return Ok(Some(step.within(index, |element| {
+                            let mut elements = elements.clone();
+                            elements[index] = Rc::new(element);
+
+                            NormalExpressionNode::Tuple(elements)
+                        })));
+                    }
+                }
+
+                Ok(None)
+            },
+            // let x = e in b behaves as (λx. b) e
+            NormalExpressionNode::Let(parameter, expression, body, function) => {
+                if strict {
+                    if let Some(step) = self.step_by_substitution(expression.as_ref())? {
+                        return Ok(Some(step.within(0, |expression| NormalExpressionNode::Let(parameter.clone(), Rc::new(expression), body.clone(), *function))));
+                    }
+                }
+
+                self.contract_redex(node)
             }
         }
     }
 
     ///
-    /// reduce
+    /// Call-by-need: an application (λx. b) a becomes let x = a in b, so that a is shared 
+    /// by every occurrence of x. The bound expression is reduced only when the body demands
+    /// x, and substituted once it is a value.
     /// 
-    pub fn reduce(&mut self, node: &NormalExpressionNode) -> NormalExpressionNode {
+    fn step_by_need(&self, node: &NormalExpressionNode) -> Stepped {
         match node {
-            // Variables and constants are already in normal form
-            NormalExpressionNode::Variable(_) => {
-                node.clone()
+            NormalExpressionNode::Variable(_) | NormalExpressionNode::Constant(_) | NormalExpressionNode::Abstraction(_, _, _) | 
+            NormalExpressionNode::Tuple(_) | NormalExpressionNode::Cons(_, _) => {
+                Ok(None)
             },
-            NormalExpressionNode::Constant(_) => {
-                node.clone()
+            NormalExpressionNode::Application(_, _) if self.saturated(node).is_some() => {
+                self.step_primitive(node, |node| self.step_by_need(node))
             },
-            // Make sure abstraction body is in NF
-            NormalExpressionNode::Abstraction(parameter, body) => {
-                let result = NormalExpressionNode::Abstraction(
-                    parameter.clone(), 
-                    Rc::new(self.reduce(body.as_ref()))
-                );
-
-                // print!("Beta Reduction: Reduced body of abstraction ({}) => ", parameter);
-                // pretty_print_normal(&result.clone()); println!();
+            NormalExpressionNode::Application(function, argument) => {
+                match function.as_ref() {
+                    NormalExpressionNode::Abstraction(parameter, body, _) => {
+                        Ok(Step::contract(ReductionRule::Beta, node, 
+                            Some(NormalExpressionNode::Let(parameter.clone(), argument.clone(), body.clone(), false))))
+                    },
+                    // Lift the binding out of the function position to keep it shared, renaming
+                    // it if it would capture a variable of the argument
+                    NormalExpressionNode::Let(parameter, expression, body, written) if self.is_answer(function.as_ref()) => {
+                        let (parameter, body) = self.rename_apart(parameter, body, &argument.free_variables());
 
-                result
-            },
-            // Application
-            NormalExpressionNode::Application(function, argument) => {                
-                // Before applying the function, we need to reduce the function and the argument
-                let function = self.reduce(function.as_ref());
-                let argument = self.reduce(argument.as_ref());
-
-                // If the function is an abstraction, we need to substitute the parameter with the argument
-                let result = match function.clone() {
-                    NormalExpressionNode::Abstraction(parameter, body) => 
-                    {
-                        self.substitute(body.as_ref(), &parameter, &argument)
-                            .map_or(
-                                NormalExpressionNode::Application(Rc::new(function), Rc::new(argument)), 
-                                |body| self.reduce(&body))
+                        Ok(Step::contract(ReductionRule::Let, node, 
+                            Some(NormalExpressionNode::Let(
+                                parameter, 
+                                expression.clone(), 
+                                Rc::new(NormalExpressionNode::Application(body, argument.clone())),
+                                *written))))
+                    },
+                    // A primitive awaiting more arguments
+                    NormalExpressionNode::Constant(Constant::Primitive(_)) => {
+                        Ok(None)
+                    },
+                    NormalExpressionNode::LetRec(_, _) if self.is_answer(function.as_ref()) => {
+                        self.contract_redex(node)
+                    },
+                    NormalExpressionNode::Constant(value) => {
+                        Err(self.apply_constant(value))
+                    },
+                    NormalExpressionNode::Tuple(_) | NormalExpressionNode::Cons(_, _) => {
+                        Err(self.apply_value(function.as_ref()))
                     },
                     _ => {
-                        NormalExpressionNode::Application(Rc::new(function), Rc::new(argument))
+                        Ok(self.step_by_need(function.as_ref())?
+                            .map(|step| step.within(0, |function| NormalExpressionNode::Application(Rc::new(function), argument.clone()))))
                     }
-                };
+                }
+            },
+            NormalExpressionNode::Arithmetic(_, _, _) => {
+                self.step_arithmetic(node, |node| self.step_by_need(node))
+            },
+            NormalExpressionNode::Conditional(_, _, _) => {
+                self.step_conditional(node, |node| self.step_by_need(node))
+            },
+            NormalExpressionNode::LetRec(_, _) => {
+                self.step_let_rec(node, |node| self.step_by_need(node))
+            },
+            NormalExpressionNode::LetTuple(_, _, _) => {
+                self.step_let_tuple(node, |node| self.step_by_need(node))
+            },
+            NormalExpressionNode::Projection(_, _) => {
+                self.step_projection(node, |node| self.step_by_need(node))
+            },
+            NormalExpressionNode::Let(_, _, _, _) => {
+                self.step_let_by_need(node, &self.demanded_parameters(node))
+            }
+        }
+    }
+
+    ///
+    /// A let under call-by-need: demanded holds, for it and each let nested in its body,
+    /// whether the body of that let demands its parameter. Working these out for the 
+    /// whole chain at once keeps a step through n nested lets linear in n.
+    /// 
+    fn step_let_by_need(&self, node: &NormalExpressionNode, demanded: &[bool]) -> Stepped {
+        let NormalExpressionNode::Let(parameter, expression, body, function) = node else {
+            return self.step_by_need(node);
+        };
 
-                // println!("Beta Reduction: Reduced application => "); pretty_print_normal(&result.clone()); println!();
+        if self.is_value(expression.as_ref()) {
+            return self.contract_redex(node);
+        }
 
-                result
-            },
-            // Arithmetic
-            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
-                let lhs = self.reduce(lhs.as_ref());
-                let rhs = self.reduce(rhs.as_ref());
+        // Bind the components of a tuple or a cons, so that they too are shared: let x = 
+        // (a, b) in e becomes let x' = a in let x'' = b in let x = (x', x'') in e
+        if let NormalExpressionNode::Tuple(_) | NormalExpressionNode::Cons(_, _) = expression.as_ref() {
+            return Ok(Step::contract(ReductionRule::Let, node, Some(self.share_components(parameter, expression, body))));
+        }
 
-                match (lhs.clone(), rhs.clone()) {
-                    (NormalExpressionNode::Constant(lhs), NormalExpressionNode::Constant(rhs)) => {
-                        match operator.as_str() {
-                            "+" => {
-                                NormalExpressionNode::Constant(lhs + rhs)
-                            },
-                            "-" => {
-                                NormalExpressionNode::Constant(lhs - rhs)
-                            },
-                            "*" => {
-                                NormalExpressionNode::Constant(lhs * rhs)
-                            },
-                            "/" => {
-                                NormalExpressionNode::Constant(lhs / rhs)
-                            },
-                            "%" => {
-                                NormalExpressionNode::Constant(lhs % rhs)
-                            },
-                            _ => {
-                                NormalExpressionNode::Arithmetic(
-                                    Rc::new(NormalExpressionNode::Constant(lhs)), 
-                                    operator.clone(), 
-                                    Rc::new(NormalExpressionNode::Constant(rhs)))
-                            }
-                        }
+        // Flatten let x = (let y = e in v) in b into let y = e in let x = v in b
+        if let NormalExpressionNode::Let(inner_parameter, inner_expression, value, inner_function) = expression.as_ref() {
+            if self.is_answer(expression.as_ref()) {
+                // b moves into the scope of y, which must not capture its variables
+                let mut variables = body.free_variables();
+                variables.remove(parameter);
+                let (inner_parameter, value) = self.rename_apart(inner_parameter, value, &variables);
+
+                return Ok(Step::contract(ReductionRule::Let, node, 
+                    Some(NormalExpressionNode::Let(
+                        inner_parameter,
+                        inner_expression.clone(),
+                        Rc::new(NormalExpressionNode::Let(parameter.clone(), value, body.clone(), *function)),
+                        *inner_function))));
+            }
+        }
+
+        if demanded[0] {
+            if let Some(step) = self.step_by_need(expression.as_ref())? {
+                return Ok(Some(step.within(0, |expression| NormalExpressionNode::Let(parameter.clone(), Rc::new(expression), body.clone(), *function))));
+            }
+        } else if let Some(step) = self.step_let_body_by_need(body.as_ref(), &demanded[1..])? {
+            return Ok(Some(step.within(1, |body| NormalExpressionNode::Let(parameter.clone(), expression.clone(), Rc::new(body), *function))));
+        }
+
+        // The body is an answer or stuck; release the binding
+        self.contract_redex(node)
+    }
+
+    fn step_let_body_by_need(&self, body: &NormalExpressionNode, demanded: &[bool]) -> Stepped {
+        match body {
+            NormalExpressionNode::Let(_, _, _, _) => self.step_let_by_need(body, demanded),
+            _ => self.step_by_need(body),
+        }
+    }
+
+    // let x = (a, b, ...) in e or let x = a :: b in e, with each component that is not a value
+    // bound by a let of its own
+    fn share_components(&self, parameter: &str, expression: &NormalExpressionNode, body: &Rc<NormalExpressionNode>) -> NormalExpressionNode {
+        let elements = match expression {
+            NormalExpressionNode::Cons(head, tail) => vec![head.clone(), tail.clone()],
+            NormalExpressionNode::Tuple(elements) => elements.clone(),
+            _ => Vec::new(),
+        };
+
+        let mut taken = body.free_variables();
+        taken.extend(elements.iter().flat_map(|element| element.free_variables()));
+        taken.insert(parameter.to_string());
+
+        let mut bindings = Vec::new();
+        let mut components = Vec::new();
+
+        for element in &elements {
+            if self.is_value(element.as_ref()) {
+                components.push(element.clone());
+                continue;
+            }
+
+            let mut name = format!("{}'", parameter);
+            while taken.contains(&name) {
+                name.push('\'');
+            }
+            taken.insert(name.clone());
+
+            components.push(Rc::new(NormalExpressionNode::Variable(name.clone())));
+            bindings.push((name, element.clone()));
+        }
+
+        let value = match expression {
+            NormalExpressionNode::Cons(_, _) => NormalExpressionNode::Cons(components[0].clone(), components[1].clone()),
+            _ => NormalExpressionNode::Tuple(components),
+        };
+        let shared = NormalExpressionNode::Let(parameter.to_string(), Rc::new(value), body.clone(), false);
+
+        bindings.into_iter().rev()
+            .fold(shared, |body, (name, element)| NormalExpressionNode::Let(name, element, Rc::new(body), false))
+    }
+
+    // Applying a constant is never meaningful
+    fn apply_constant(&self, value: &Constant) -> AlabastaError {
+        AlabastaError::Type(format!("the constant {} cannot be applied to an argument", value))
+    }
+
+    // Nor is applying a tuple or a list
+    fn apply_value(&self, node: &NormalExpressionNode) -> AlabastaError {
+        AlabastaError::Type(format!("{} cannot be applied to an argument", self.kind(node).unwrap_or("a value")))
+    }
+
+    // Values may be substituted without duplicating work, as may tuples and lists of values
+    fn is_value(&self, node: &NormalExpressionNode) -> bool {
+        match node {
+            NormalExpressionNode::Variable(_) | 
+            NormalExpressionNode::Constant(_) | 
+            NormalExpressionNode::Abstraction(_, _, _) => true,
+            NormalExpressionNode::Tuple(elements) => elements.iter().all(|element| self.is_value(element.as_ref())),
+            NormalExpressionNode::Cons(head, tail) => self.is_value(head.as_ref()) && self.is_value(tail.as_ref()),
+            _ => false
+        }
+    }
+
+    // An answer is a value, possibly wrapped in let bindings
+    fn is_answer(&self, node: &NormalExpressionNode) -> bool {
+        match node {
+            NormalExpressionNode::Constant(_) | NormalExpressionNode::Abstraction(_, _, _) | 
+            NormalExpressionNode::Tuple(_) | NormalExpressionNode::Cons(_, _) => true,
+            NormalExpressionNode::Let(_, _, body, _) | NormalExpressionNode::LetRec(_, body) => self.is_answer(body.as_ref()),
+            _ => false
+        }
+    }
+
+    // For a let and each let nested directly in its body, outermost first, whether the
+    // body of that let demands its parameter
+    fn demanded_parameters(&self, node: &NormalExpressionNode) -> Vec<bool> {
+        let mut chain = Vec::new();
+        let mut innermost = node;
+
+        while let NormalExpressionNode::Let(parameter, expression, body, _) = innermost {
+            chain.push((parameter, expression));
+            innermost = body.as_ref();
+        }
+
+        let mut demand = self.demand(innermost);
+        let mut demanded = vec![false; chain.len()];
+
+        for (index, (parameter, expression)) in chain.into_iter().enumerate().rev() {
+            demanded[index] = demand == Some(parameter);
+
+            if demanded[index] {
+                demand = self.demand(expression.as_ref());
+            }
+        }
+
+        demanded
+    }
+
+    // The variable whose value is needed before the expression can make progress
+    fn demand<'a>(&self, node: &'a NormalExpressionNode) -> Option<&'a String> {
+        match node {
+            NormalExpressionNode::Variable(name) => Some(name),
+            NormalExpressionNode::Application(function, _) => {
+                match self.saturated(node) {
+                    Some((primitive, arguments)) => {
+                        self.pending(primitive, &arguments).and_then(|index| self.demand(arguments[index].as_ref()))
                     },
-                    _ => {
-                        NormalExpressionNode::Arithmetic(Rc::new(lhs), operator.clone(), Rc::new(rhs))
-                    }
+                    None => self.demand(function.as_ref())
+                }
+            },
+            NormalExpressionNode::Arithmetic(lhs, _, rhs) => {
+                match lhs.as_ref() {
+                    NormalExpressionNode::Constant(_) => self.demand(rhs.as_ref()),
+                    _ => self.demand(lhs.as_ref())
+                }
+            },
+            NormalExpressionNode::Let(parameter, expression, body, _) => {
+                match self.demand(body.as_ref()) {
+                    Some(name) if name == parameter => self.demand(expression.as_ref()),
+                    demanded => demanded
+                }
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                match self.demand(body.as_ref()) {
+                    Some(name) if bindings.iter().any(|(parameter, _, _)| parameter == name) => None,
+                    demanded => demanded
                 }
             },
-            NormalExpressionNode::Let(parameter, expression, body) => {
-                let expression = self.reduce(expression.as_ref());
-                let body = self.reduce(body.as_ref());
+            NormalExpressionNode::LetTuple(_, expression, _) => self.demand(expression.as_ref()),
+            NormalExpressionNode::Conditional(condition, _, _) => self.demand(condition.as_ref()),
+            NormalExpressionNode::Projection(tuple, _) => self.demand(tuple.as_ref()),
+            _ => None
+        }
+    }
+
+    // The expression after the given number of contractions
+    fn replay(&self, node: &NormalExpressionNode, steps: usize) -> Result<NormalExpressionNode, AlabastaError> {
+        let mut result = node.clone();
+
+        for _ in 0..steps {
+            match self.next_contraction(&result)? {
+                Some(contraction) => result = contraction.after,
+                None => break,
+            }
+        }
+
+        Ok(result)
+    }
+
+    ///
+    /// reduce
+    /// 
+    /// Contracts redexes until none remain, the fuel runs out, or the expression returns 
+    /// to an α-equivalent expression seen earlier (in which case it can never normalise).
+    /// When tracing is enabled, every contraction performed is recorded and can be
+    /// retrieved through trace() until the next call. Divergence is reported as a 
+    /// ResourceLimit error.
+    /// 
+    pub fn reduce(&mut self, node: &NormalExpressionNode) -> Result<NormalExpressionNode, AlabastaError> {
+        let mut result = node.clone();
+        let mut steps = 0;
+
+        // The size and hash of each expression seen so far, with the step it was seen at.
+        // Keeping every expression would hold on to a copy of the path to each redex 
+        // contracted, so an expression that matches one of these is compared with the
+        // earlier one by reducing the original expression again as far as it.
+        let mut seen: HashMap<(usize, u64), usize> = HashMap::new();
+
+        self.contractions.clear();
+
+        loop {
+            let size = result.size();
+
+            let mut hasher = Fingerprint::default();
+            result.hash(&mut hasher);
 
-                let result = self.substitute(&body, parameter, &expression)
-                    .map_or(
-                        NormalExpressionNode::Let(parameter.clone(), Rc::new(expression), Rc::new(body)), 
-                        |body| self.reduce(&body));
+            match seen.entry((size, hasher.finish())) {
+                Entry::Occupied(entry) if self.replay(node, *entry.get())? == result => {
+                    return Err(AlabastaError::ResourceLimit(Divergence::Cycle { steps, earlier: *entry.get(), term: result }));
+                },
+                Entry::Occupied(_) => { },
+                Entry::Vacant(entry) => {
+                    entry.insert(steps);
+                }
+            }
+
+            if size > self.fuel.size {
+                return Err(AlabastaError::ResourceLimit(Divergence::SizeLimit { steps, size: self.fuel.size, term: result }));
+            }
+
+            let Some(contraction) = self.next_contraction(&result)? else {
+                return Ok(result);
+            };
+
+            if steps >= self.fuel.steps {
+                return Err(AlabastaError::ResourceLimit(Divergence::StepLimit { steps, term: result }));
+            }
+
+            result = contraction.after.clone();
+            steps += 1;
+
+            if self.tracing {
+                self.contractions.push(contraction);
+            }
+        }
+    }
+
+    ///
+    /// As reduce, for an expression in nameless form, by a round trip through named form:
+    /// the binders are given names, the named expression is reduced by reduce, and its
+    /// normal form is converted back. The nameless result is the same whichever names are
+    /// chosen. Nameless expressions are never reduced as they stand.
+    /// 
+    pub fn reduce_nameless(&mut self, node: &NamelessExpressionNode) -> Result<NamelessExpressionNode, AlabastaError> {
+        self.reduce(&from_de_bruijn(node))
+            .map(|result| to_de_bruijn(&result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::encoding::encode_lists;
+    use crate::environment::Environment;
+
+    // Terms that never normalise, with the strategies under which they run away. Under 
+    // call-by-name and call-by-need, Y (λy. λz. y) stops at an abstraction.
+    const DIVERGENT: [(&str, &[ReductionStrategy]); 5] = [
+        (r"(\x. x x) (\x. x x)", &ReductionStrategy::ALL),
+        (r"(\x. x x x) (\x. x x x)", &ReductionStrategy::ALL),
+        (r"(\f. (\x. f (x x)) (\x. f (x x))) (\y. \z. y)", &[
+            ReductionStrategy::NormalOrder,
+            ReductionStrategy::ApplicativeOrder,
+            ReductionStrategy::CallByValue,
+        ]),
+        (r"let rec f n = 1 + f n in f 0", &ReductionStrategy::ALL),
+        (r"fix (\f n. 1 + f n) 0", &ReductionStrategy::ALL),
+    ];
+
+    // Recursion written with let rec and with fix, recurring under an abstraction or in an
+    // argument of a stuck application
+    const RECURSIVE: [(&str, &str, ListEncoding); 3] = [
+        (
+            r"let rec f n = if n == 0 then 0 else f (n - 1) in \y. f 3",
+            r"\y. fix (\f n. if n == 0 then 0 else f (n - 1)) 3",
+            ListEncoding::Native,
+        ),
+        (
+            r"let rec m f l = l [] (\h t. f h :: m f t) in m (\x. x + 1) [1, 2]",
+            r"fix (\m f l. l [] (\h t. f h :: m f t)) (\x. x + 1) [1, 2]",
+            ListEncoding::Scott,
+        ),
+        (
+            r"let rec f n = if n == 0 then [] else n :: f (n - 1) in f 3",
+            r"fix (\f n. if n == 0 then [] else n :: f (n - 1)) 3",
+            ListEncoding::Church,
+        ),
+    ];
+
+    // Terms with x free under a binder, between them of every kind of node
+    const SUBSTITUTED: [&str; 9] = [
+        r"\y. x y",
+        r"\y. x + y",
+        r"let y = 1 in x y",
+        r"let rec f n = x f n and g = f in g",
+        r"let (a, b) = (x, 1) in \c. x a b c",
+        r"\y. (x, y).1",
+        r"if x then \y. x y else 1",
+        r"\h. x :: h :: nil",
+        r"\y. 1",
+    ];
+
+    // Pairs of terms that differ only in the names of their binders, and pairs that do not
+    const ALPHA_EQUIVALENT: [(&str, &str); 5] = [
+        (r"\x. x", r"\y. y"),
+        (r"\x y. x y z", r"\a b. a b z"),
+        (r"let x = 1 in \y. x", r"let a = 1 in \b. a"),
+        (r"let rec f n = g n and g n = f n in f", r"let rec p m = q m and q k = p k in p"),
+        (r"let (a, b) = (1, 2) in a :: b :: nil", r"let (c, d) = (1, 2) in c :: d :: nil"),
+    ];
+
+    const ALPHA_DISTINCT: [(&str, &str); 4] = [
+        (r"\x y. x", r"\x y. y"),
+        (r"\x. y", r"\x. z"),
+        (r"let rec f = 1 and g = 2 in f", r"let rec f = 1 and g = 2 in g"),
+        (r"let (a, b) = (1, 2) in a", r"let (a, b) = (1, 2) in b"),
+    ];
+
+    fn normal_form(source: &str) -> NormalExpressionNode {
+        encoded(source, ListEncoding::Native)
+    }
+
+    fn encoded(source: &str, lists: ListEncoding) -> NormalExpressionNode {
+        let mut environment = Environment::new();
+        let term = environment.term(source);
+
+        encode_lists(&term, lists, &mut environment)
+    }
+
+    // As in the session, deep terms need a deep stack
+    fn with_deep_stack(test: fn()) {
+        thread::Builder::new()
+            .stack_size(crate::STACK_SIZE)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn let_rec_and_fix_reach_the_same_normal_form() {
+        with_deep_stack(|| {
+            for (recursive, fixed, lists) in RECURSIVE {
+                for strategy in [ReductionStrategy::NormalOrder, ReductionStrategy::ApplicativeOrder] {
+                    let recursive_form = BetaReducer::new(strategy).reduce(&encoded(recursive, lists)).unwrap();
+                    let fixed_form = BetaReducer::new(strategy).reduce(&encoded(fixed, lists)).unwrap();
 
-                // print!("Beta Reduction: Reduced let expression => "); pretty_print_normal(&result.clone()); println!();
+                    assert!(!matches!(recursive_form, NormalExpressionNode::LetRec(_, _)), "`{}` under {}: {}", recursive, strategy, recursive_form);
+                    assert_eq!(recursive_form, fixed_form, "`{}` and `{}` under {}", recursive, fixed, strategy);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn length_takes_strings_and_encoded_lists() {
+        with_deep_stack(|| {
+            for lists in ListEncoding::ALL {
+                for strategy in ReductionStrategy::ALL {
+                    let mut reducer = BetaReducer::new(strategy);
+                    reducer.set_lists(lists);
+
+                    let length = reducer.reduce(&encoded(r#"length "abc" + length (map (\x. x) [1, 2])"#, lists)).unwrap();
+                    assert_eq!(length, normal_form("5"), "under {} with {}", strategy, lists);
+                }
+            }
+        });
+    }
 
-                result
+    fn hash(node: &NormalExpressionNode) -> u64 {
+        let mut hasher = Fingerprint::default();
+        node.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn substitution_avoids_capture() {
+        let reducer = BetaReducer::new(ReductionStrategy::NormalOrder);
+
+        for source in SUBSTITUTED {
+            let term = normal_form(source);
+            let free = term.free_variables();
+
+            // Substituting the name of each binder for x, it stays free
+            for binder in term.names().difference(&free) {
+                let result = reducer.substitute(&term, "x", &NormalExpressionNode::Variable(binder.clone()));
+
+                let mut expected = free.clone();
+                if expected.remove("x") {
+                    expected.insert(binder.clone());
+                }
+
+                assert_eq!(result.free_variables(), expected, "`{}` with x as {}", source, binder);
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn alpha_equivalent_terms_compare_and_hash_equal() {
+        for (lhs, rhs) in ALPHA_EQUIVALENT {
+            let mut environment = Environment::new();
+            let (lhs, rhs) = (environment.term(lhs), environment.term(rhs));
+
+            assert_eq!(lhs, rhs);
+            assert_eq!(hash(&lhs), hash(&rhs), "`{}` and `{}`", lhs, rhs);
+        }
+
+        for (lhs, rhs) in ALPHA_DISTINCT {
+            assert_ne!(normal_form(lhs), normal_form(rhs));
+        }
+    }
+
+    #[test]
+    fn partial_results_are_elided_in_depth_and_size() {
+        let term = normal_form("1 + (1 + (1 + 1))");
+
+        assert_eq!(term.elided(2, 100).to_string(), "1 + (… + …)");
+        assert_eq!(term.elided(100, 4).to_string(), "1 + (1 + …)");
+        assert_eq!(term.elided(100, 100), term);
+    }
+
+    #[test]
+    fn divergent_terms_run_out_of_fuel() {
+        with_deep_stack(|| {
+            let fuel = Fuel { steps: 1_000, size: 1_000 };
+
+            for (source, strategies) in DIVERGENT {
+                for &strategy in strategies {
+                    let mut reducer = BetaReducer::new(strategy);
+                    reducer.set_fuel(fuel);
+
+                    match reducer.reduce(&normal_form(source)) {
+                        Err(AlabastaError::ResourceLimit(_)) => { },
+                        other => panic!("`{}` under {}: expected a resource limit, got {:?}", source, strategy, other),
+                    }
+                }
+            }
+        });
+    }
+}
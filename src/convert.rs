@@ -4,103 +4,41 @@ use crate::{
     beta::NormalExpressionNode, 
     ast::{
         ExpressionNode, 
-        VariableNode, 
-        ConstantNode, 
-        AbstractionNode, 
-        ApplicationNode, 
-        ArithmeticNode, 
-        LetNode
+        Constant
     }
 };
 
-pub fn from_normal_form(node: &NormalExpressionNode) -> ExpressionNode {
-    match node {
-        NormalExpressionNode::Variable(name) => {
-            ExpressionNode::Variable(VariableNode::new(name.as_str()))
-        },
-        NormalExpressionNode::Constant(value) => {
-            ExpressionNode::Constant(ConstantNode{ value: *value })
-        },
-        NormalExpressionNode::Abstraction(parameter, body) => {
-            ExpressionNode::Abstraction(AbstractionNode
-                { 
-                    variable: Rc::new(VariableNode::new(parameter.as_str())),
-                    expression: Rc::new(from_normal_form(body.as_ref()))
-                }
-            )
-        },
-        NormalExpressionNode::Application(function, argument) => {
-            ExpressionNode::Application(ApplicationNode
-                { 
-                    function: Rc::new(from_normal_form(function.as_ref())),
-                    argument: Rc::new(from_normal_form(argument.as_ref()))
-                }
-            )
-        },
-        NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
-            ExpressionNode::Arithmetic(ArithmeticNode
-                {
-                    operator: operator.clone(),
-                    left: Rc::new(from_normal_form(lhs.as_ref())),
-                    right: Rc::new(from_normal_form(rhs.as_ref()))
-                }
-            )
-        },
-        NormalExpressionNode::Let(variable, expression, scope) => {
-            ExpressionNode::Let(LetNode
-                {
-                    variable: VariableNode::new(variable.as_str()),
-                    expression: Rc::new(from_normal_form(expression.as_ref())),
-                    scope: Rc::new(from_normal_form(scope.as_ref()))
-                }
-            )
-        },
-    }    
-}
-
 pub fn to_normal_form(node: &ExpressionNode) -> NormalExpressionNode {
     match node {
         ExpressionNode::Variable(node) => {
             NormalExpressionNode::Variable(node.name.borrow().clone())
         },
         ExpressionNode::Constant(node) => {
-            NormalExpressionNode::Constant(node.value)
+            NormalExpressionNode::Constant(node.value.clone())
         },
         ExpressionNode::Abstraction(node) => {
             let body = to_normal_form(node.expression.as_ref());
             let parameter = node.variable.name.borrow().clone();                
-            NormalExpressionNode::Abstraction(parameter, Rc::new(body))
+            NormalExpressionNode::Abstraction(parameter, Rc::new(body), node.curried)
         },
         ExpressionNode::Application(node) => {
             let function = to_normal_form(node.function.as_ref());
             let argument = to_normal_form(node.argument.as_ref());
             NormalExpressionNode::Application(Rc::new(function), Rc::new(argument))
         },
+        // h :: t builds a list rather than computing a value
+        ExpressionNode::Arithmetic(node) if node.operator == "::" => {
+            let head = to_normal_form(node.left.as_ref());
+            let tail = to_normal_form(node.right.as_ref());
+
+            NormalExpressionNode::Cons(Rc::new(head), Rc::new(tail))
+        },
         ExpressionNode::Arithmetic(node) => {
             let lhs = to_normal_form(node.left.as_ref());
             let rhs = to_normal_form(node.right.as_ref());
+            let operator = node.operator.clone();
 
-            let result = match (lhs, rhs) {
-                (NormalExpressionNode::Constant(a), NormalExpressionNode::Constant(b)) => { 
-                    match node.operator.as_str() {
-                        "+" => NormalExpressionNode::Constant(a + b),
-                        "-" => NormalExpressionNode::Constant(a - b),
-                        "*" => NormalExpressionNode::Constant(a * b),
-                        "/" => NormalExpressionNode::Constant(a / b),
-                        "%" => NormalExpressionNode::Constant(a % b),
-                        _ => panic!("Unknown operator"),
-                    }
-                }
-                (_, _) => { 
-                    let lhs = to_normal_form(node.left.as_ref());
-                    let rhs = to_normal_form(node.right.as_ref());
-                    let operator = node.operator.clone();
-                    
-                    NormalExpressionNode::Arithmetic(Rc::new(lhs), operator, Rc::new(rhs))
-                }
-            };
-
-            result
+            NormalExpressionNode::Arithmetic(Rc::new(lhs), operator, Rc::new(rhs))
         },
         // let var = expr in expr
         ExpressionNode::Let(node) => {
@@ -108,7 +46,60 @@ pub fn to_normal_form(node: &ExpressionNode) -> NormalExpressionNode {
             let expression = to_normal_form(node.expression.as_ref());
             let body = to_normal_form(node.scope.as_ref());
 
-            NormalExpressionNode::Let(parameter, Rc::new(expression), Rc::new(body))
+            NormalExpressionNode::Let(parameter, Rc::new(expression), Rc::new(body), node.function)
+        },
+        // let rec var = expr and ... in expr
+        ExpressionNode::LetRec(node) => {
+            let bindings = node.bindings.iter()
+                .map(|binding| (binding.variable.name.borrow().clone(), Rc::new(to_normal_form(binding.expression.as_ref())), binding.function))
+                .collect();
+            let body = to_normal_form(node.scope.as_ref());
+
+            NormalExpressionNode::LetRec(bindings, Rc::new(body))
+        },
+        // if expr then expr else expr
+        ExpressionNode::Conditional(node) => {
+            let condition = to_normal_form(node.condition.as_ref());
+            let consequent = to_normal_form(node.consequent.as_ref());
+            let alternative = to_normal_form(node.alternative.as_ref());
+
+            NormalExpressionNode::Conditional(Rc::new(condition), Rc::new(consequent), Rc::new(alternative))
+        },
+        // let (var, var, ...) = expr in expr
+        ExpressionNode::LetTuple(node) => {
+            let parameters = node.variables.iter()
+                .map(|variable| variable.name.borrow().clone())
+                .collect();
+            let expression = to_normal_form(node.expression.as_ref());
+            let body = to_normal_form(node.scope.as_ref());
+
+            NormalExpressionNode::LetTuple(parameters, Rc::new(expression), Rc::new(body))
+        },
+        // (expr, expr, ...)
+        ExpressionNode::Tuple(node) => {
+            let elements = node.elements.iter()
+                .map(|element| Rc::new(to_normal_form(element.as_ref())))
+                .collect();
+
+            NormalExpressionNode::Tuple(elements)
+        },
+        // expr.n
+        ExpressionNode::Projection(node) => {
+            let tuple = to_normal_form(node.tuple.as_ref());
+
+            NormalExpressionNode::Projection(Rc::new(tuple), node.index)
+        },
+        // [expr, expr, ...], the elements consed onto the empty list
+        ExpressionNode::List(node) => {
+            node.elements.iter().rev()
+                .fold(NormalExpressionNode::Constant(Constant::Nil), |tail, element| {
+                    NormalExpressionNode::Cons(Rc::new(to_normal_form(element.as_ref())), Rc::new(tail))
+                })
+        },
+        // Input with error nodes is reported rather than evaluated; should one slip 
+        // through, it reduces no further than an unbound variable would
+        ExpressionNode::Error(_) => {
+            NormalExpressionNode::Variable("<error>".to_string())
         },
     }        
 }
\ No newline at end of file
@@ -0,0 +1,278 @@
+//!
+//! De Bruijn indices
+//!
+//! A nameless representation of expressions, in which a bound variable is replaced by the
+//! number of binders between it and its own binder (0 for the innermost). α-equivalent
+//! expressions, such as \x. x and \y. y, have the same nameless form, printed λ. #0.
+//!
+//! The nameless form is for display only: there is no shifting or substitution on it.
+//! Nameless expressions are reduced by naming their binders, reducing the named expression
+//! and converting the result back (see BetaReducer::reduce_nameless).
+//!
+
+use std::{
+    collections::HashSet,
+    rc::Rc
+};
+
+use crate::ast::Constant;
+use crate::beta::NormalExpressionNode;
+
+///
+/// Nameless Expression Node
+///
+/// Free variables keep their names. Abstractions and let expressions bind index 0 in
+/// their body and scope respectively; the bound expression of a let is outside its scope.
+/// The bindings of a recursive let are in scope in its bound expressions and its body,
+/// where the last binding has index 0. A tuple pattern binds as many indices as it has
+/// variables in its scope, the last variable having index 0.
+///
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum NamelessExpressionNode {
+    Free(String),
+    Bound(usize),
+    Constant(Constant),
+    Abstraction(Rc<NamelessExpressionNode>),
+    Application(Rc<NamelessExpressionNode>, Rc<NamelessExpressionNode>),
+    Arithmetic(Rc<NamelessExpressionNode>, String, Rc<NamelessExpressionNode>),
+    Let(Rc<NamelessExpressionNode>, Rc<NamelessExpressionNode>),
+    LetRec(Vec<Rc<NamelessExpressionNode>>, Rc<NamelessExpressionNode>),
+    LetTuple(usize, Rc<NamelessExpressionNode>, Rc<NamelessExpressionNode>),
+    Conditional(Rc<NamelessExpressionNode>, Rc<NamelessExpressionNode>, Rc<NamelessExpressionNode>),
+    Tuple(Vec<Rc<NamelessExpressionNode>>),
+    Projection(Rc<NamelessExpressionNode>, usize),
+    Cons(Rc<NamelessExpressionNode>, Rc<NamelessExpressionNode>),
+}
+
+pub fn to_de_bruijn(node: &NormalExpressionNode) -> NamelessExpressionNode {
+    to_de_bruijn_within(node, &mut Vec::new())
+}
+
+fn to_de_bruijn_within(node: &NormalExpressionNode, scope: &mut Vec<String>) -> NamelessExpressionNode {
+    match node {
+        NormalExpressionNode::Variable(name) => {
+            match scope.iter().rposition(|variable| variable == name) {
+                Some(depth) => NamelessExpressionNode::Bound(scope.len() - 1 - depth),
+                None => NamelessExpressionNode::Free(name.clone()),
+            }
+        },
+        NormalExpressionNode::Constant(value) => {
+            NamelessExpressionNode::Constant(value.clone())
+        },
+        NormalExpressionNode::Abstraction(parameter, body, _) => {
+            scope.push(parameter.clone());
+            let body = to_de_bruijn_within(body.as_ref(), scope);
+            scope.pop();
+
+            NamelessExpressionNode::Abstraction(Rc::new(body))
+        },
+        NormalExpressionNode::Application(function, argument) => {
+            NamelessExpressionNode::Application(
+                Rc::new(to_de_bruijn_within(function.as_ref(), scope)),
+                Rc::new(to_de_bruijn_within(argument.as_ref(), scope)))
+        },
+        NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+            NamelessExpressionNode::Arithmetic(
+                Rc::new(to_de_bruijn_within(lhs.as_ref(), scope)),
+                operator.clone(),
+                Rc::new(to_de_bruijn_within(rhs.as_ref(), scope)))
+        },
+        NormalExpressionNode::Let(parameter, expression, body, _) => {
+            let expression = to_de_bruijn_within(expression.as_ref(), scope);
+
+            scope.push(parameter.clone());
+            let body = to_de_bruijn_within(body.as_ref(), scope);
+            scope.pop();
+
+            NamelessExpressionNode::Let(Rc::new(expression), Rc::new(body))
+        },
+        NormalExpressionNode::LetRec(bindings, body) => {
+            scope.extend(bindings.iter().map(|(parameter, _, _)| parameter.clone()));
+
+            let expressions = bindings.iter()
+                .map(|(_, expression, _)| Rc::new(to_de_bruijn_within(expression.as_ref(), scope)))
+                .collect();
+            let body = to_de_bruijn_within(body.as_ref(), scope);
+
+            scope.truncate(scope.len() - bindings.len());
+
+            NamelessExpressionNode::LetRec(expressions, Rc::new(body))
+        },
+        NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+            NamelessExpressionNode::Conditional(
+                Rc::new(to_de_bruijn_within(condition.as_ref(), scope)),
+                Rc::new(to_de_bruijn_within(consequent.as_ref(), scope)),
+                Rc::new(to_de_bruijn_within(alternative.as_ref(), scope)))
+        },
+        NormalExpressionNode::LetTuple(parameters, expression, body) => {
+            let expression = to_de_bruijn_within(expression.as_ref(), scope);
+
+            scope.extend(parameters.iter().cloned());
+            let body = to_de_bruijn_within(body.as_ref(), scope);
+            scope.truncate(scope.len() - parameters.len());
+
+            NamelessExpressionNode::LetTuple(parameters.len(), Rc::new(expression), Rc::new(body))
+        },
+        NormalExpressionNode::Tuple(elements) => {
+            NamelessExpressionNode::Tuple(elements.iter()
+                .map(|element| Rc::new(to_de_bruijn_within(element.as_ref(), scope)))
+                .collect())
+        },
+        NormalExpressionNode::Projection(tuple, index) => {
+            NamelessExpressionNode::Projection(Rc::new(to_de_bruijn_within(tuple.as_ref(), scope)), *index)
+        },
+        NormalExpressionNode::Cons(head, tail) => {
+            NamelessExpressionNode::Cons(
+                Rc::new(to_de_bruijn_within(head.as_ref(), scope)),
+                Rc::new(to_de_bruijn_within(tail.as_ref(), scope)))
+        },
+    }
+}
+
+///
+/// Name the binders of a nameless expression after their depth (@x0 for the outermost),
+/// priming a name should it clash with a free variable
+///
+pub fn from_de_bruijn(node: &NamelessExpressionNode) -> NormalExpressionNode {
+    let mut free = HashSet::new();
+    node.collect_free_variables(&mut free);
+
+    from_de_bruijn_within(node, &mut Vec::new(), &free)
+}
+
+fn from_de_bruijn_within(node: &NamelessExpressionNode, scope: &mut Vec<String>, free: &HashSet<String>) -> NormalExpressionNode {
+    let bind = |scope: &mut Vec<String>| {
+        let mut name = format!("@x{}", scope.len());
+        while free.contains(&name) {
+            name.push('\'');
+        }
+
+        scope.push(name.clone());
+        name
+    };
+
+    match node {
+        NamelessExpressionNode::Free(name) => {
+            NormalExpressionNode::Variable(name.clone())
+        },
+        NamelessExpressionNode::Bound(index) => {
+            // An index without a binder can only come from a malformed term; keep it visible
+            match scope.len().checked_sub(index + 1) {
+                Some(depth) => NormalExpressionNode::Variable(scope[depth].clone()),
+                None => NormalExpressionNode::Variable(format!("#{}", index)),
+            }
+        },
+        NamelessExpressionNode::Constant(value) => {
+            NormalExpressionNode::Constant(value.clone())
+        },
+        NamelessExpressionNode::Abstraction(body) => {
+            let parameter = bind(scope);
+            let body = from_de_bruijn_within(body.as_ref(), scope, free);
+            scope.pop();
+
+            NormalExpressionNode::Abstraction(parameter, Rc::new(body), false)
+        },
+        NamelessExpressionNode::Application(function, argument) => {
+            NormalExpressionNode::Application(
+                Rc::new(from_de_bruijn_within(function.as_ref(), scope, free)),
+                Rc::new(from_de_bruijn_within(argument.as_ref(), scope, free)))
+        },
+        NamelessExpressionNode::Arithmetic(lhs, operator, rhs) => {
+            NormalExpressionNode::Arithmetic(
+                Rc::new(from_de_bruijn_within(lhs.as_ref(), scope, free)),
+                operator.clone(),
+                Rc::new(from_de_bruijn_within(rhs.as_ref(), scope, free)))
+        },
+        NamelessExpressionNode::Let(expression, body) => {
+            let expression = from_de_bruijn_within(expression.as_ref(), scope, free);
+
+            let parameter = bind(scope);
+            let body = from_de_bruijn_within(body.as_ref(), scope, free);
+            scope.pop();
+
+            NormalExpressionNode::Let(parameter, Rc::new(expression), Rc::new(body), false)
+        },
+        NamelessExpressionNode::LetRec(expressions, body) => {
+            let parameters: Vec<String> = expressions.iter().map(|_| bind(scope)).collect();
+
+            let bindings = parameters.into_iter()
+                .zip(expressions.iter())
+                .map(|(parameter, expression)| (parameter, Rc::new(from_de_bruijn_within(expression.as_ref(), scope, free)), false))
+                .collect();
+            let body = from_de_bruijn_within(body.as_ref(), scope, free);
+
+            scope.truncate(scope.len() - expressions.len());
+
+            NormalExpressionNode::LetRec(bindings, Rc::new(body))
+        },
+        NamelessExpressionNode::Conditional(condition, consequent, alternative) => {
+            NormalExpressionNode::Conditional(
+                Rc::new(from_de_bruijn_within(condition.as_ref(), scope, free)),
+                Rc::new(from_de_bruijn_within(consequent.as_ref(), scope, free)),
+                Rc::new(from_de_bruijn_within(alternative.as_ref(), scope, free)))
+        },
+        NamelessExpressionNode::LetTuple(arity, expression, body) => {
+            let expression = from_de_bruijn_within(expression.as_ref(), scope, free);
+
+            let parameters = (0..*arity).map(|_| bind(scope)).collect();
+            let body = from_de_bruijn_within(body.as_ref(), scope, free);
+            scope.truncate(scope.len() - arity);
+
+            NormalExpressionNode::LetTuple(parameters, Rc::new(expression), Rc::new(body))
+        },
+        NamelessExpressionNode::Tuple(elements) => {
+            NormalExpressionNode::Tuple(elements.iter()
+                .map(|element| Rc::new(from_de_bruijn_within(element.as_ref(), scope, free)))
+                .collect())
+        },
+        NamelessExpressionNode::Projection(tuple, index) => {
+            NormalExpressionNode::Projection(Rc::new(from_de_bruijn_within(tuple.as_ref(), scope, free)), *index)
+        },
+        NamelessExpressionNode::Cons(head, tail) => {
+            NormalExpressionNode::Cons(
+                Rc::new(from_de_bruijn_within(head.as_ref(), scope, free)),
+                Rc::new(from_de_bruijn_within(tail.as_ref(), scope, free)))
+        },
+    }
+}
+
+impl NamelessExpressionNode {
+    fn collect_free_variables(&self, free: &mut HashSet<String>) {
+        match self {
+            NamelessExpressionNode::Free(name) => {
+                free.insert(name.clone());
+            },
+            NamelessExpressionNode::Bound(_) | NamelessExpressionNode::Constant(_) => { },
+            NamelessExpressionNode::Abstraction(body) => {
+                body.collect_free_variables(free);
+            },
+            NamelessExpressionNode::Application(lhs, rhs) |
+            NamelessExpressionNode::Arithmetic(lhs, _, rhs) |
+            NamelessExpressionNode::Let(lhs, rhs) |
+            NamelessExpressionNode::LetTuple(_, lhs, rhs) |
+            NamelessExpressionNode::Cons(lhs, rhs) => {
+                lhs.collect_free_variables(free);
+                rhs.collect_free_variables(free);
+            },
+            NamelessExpressionNode::LetRec(expressions, body) => {
+                for expression in expressions {
+                    expression.collect_free_variables(free);
+                }
+                body.collect_free_variables(free);
+            },
+            NamelessExpressionNode::Conditional(condition, consequent, alternative) => {
+                condition.collect_free_variables(free);
+                consequent.collect_free_variables(free);
+                alternative.collect_free_variables(free);
+            },
+            NamelessExpressionNode::Tuple(elements) => {
+                for element in elements {
+                    element.collect_free_variables(free);
+                }
+            },
+            NamelessExpressionNode::Projection(tuple, _) => {
+                tuple.collect_free_variables(free);
+            },
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+///
+/// Span
+///
+/// A region of source text: start and end offsets into the input, and the line and
+/// column (both from 1) at which it starts.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    // The span covering this one through to the end of another
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..*self
+        }
+    }
+}
+
+///
+/// Error codes, grouped by the stage reporting them
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    // Lexical analysis
+    UnexpectedCharacter,
+    IntegerTooLarge,
+    UnterminatedComment,
+    UnterminatedLiteral,
+    InvalidLiteral,
+    // Parsing
+    UnexpectedToken,
+    UnexpectedEnd,
+    DuplicateBinder,
+    // Evaluation
+    DivisionByZero,
+    Overflow,
+    TypeMismatch,
+    Unbound,
+    ResourceLimit,
+    // Input and output
+    Io,
+}
+
+impl ErrorCode {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedCharacter => "E0001",
+            ErrorCode::IntegerTooLarge => "E0002",
+            ErrorCode::UnterminatedComment => "E0003",
+            ErrorCode::UnterminatedLiteral => "E0004",
+            ErrorCode::InvalidLiteral => "E0005",
+            ErrorCode::UnexpectedToken => "E0100",
+            ErrorCode::UnexpectedEnd => "E0101",
+            ErrorCode::DuplicateBinder => "E0102",
+            ErrorCode::DivisionByZero => "E0200",
+            ErrorCode::Overflow => "E0201",
+            ErrorCode::TypeMismatch => "E0202",
+            ErrorCode::Unbound => "E0203",
+            ErrorCode::ResourceLimit => "E0204",
+            ErrorCode::Io => "E0300",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.code())
+    }
+}
+
+///
+/// Diagnostic
+///
+/// An error located in the source: a message, and a label shown under the offending text.
+/// Once the source is known (see located), the diagnostic displays the offending line.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub message: String,
+    pub label: String,
+    pub span: Span,
+    // The origin (file name or <input>) and text of the line the span starts on
+    context: Option<Box<(String, String)>>,
+}
+
+impl Diagnostic {
+    pub fn new(code: ErrorCode, message: String, label: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            code,
+            message,
+            label,
+            span,
+            context: None,
+        }
+    }
+
+    ///
+    /// An "expected X, found Y" diagnostic
+    ///
+    pub fn expected(expected: &str, found: &str, span: Span) -> Diagnostic {
+        let code = if found == "end of input" { ErrorCode::UnexpectedEnd } else { ErrorCode::UnexpectedToken };
+
+        Diagnostic::new(
+            code,
+            format!("expected {}, found {}", expected, found),
+            format!("expected {}", expected),
+            span)
+    }
+
+    ///
+    /// Attach the source the diagnostic refers to, so that it displays the offending line
+    /// with the span underlined:
+    ///
+    ///     [E0100] expected `in`, found `)`
+    ///      --> <input>:1:11
+    ///       |
+    ///     1 | let x = 5 )
+    ///       |           ^ expected `in`
+    ///
+    pub fn located(mut self, source: &str, origin: &str) -> Diagnostic {
+        let line = source.lines().nth(self.span.line.saturating_sub(1)).unwrap_or("");
+
+        self.context = Some(Box::new((origin.to_string(), line.to_string())));
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)?;
+
+        let Some((origin, line)) = self.context.as_deref() else {
+            return Ok(());
+        };
+
+        let gutter = " ".repeat(self.span.line.to_string().len());
+
+        // Underline up to the end of the span, or of the line if the span continues past it;
+        // columns count characters
+        let width = line.chars().count();
+        let offset = self.span.column.saturating_sub(1).min(width);
+        let length = (self.span.end.saturating_sub(self.span.start))
+            .min(width.saturating_sub(offset))
+            .max(1);
+
+        write!(f, "\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{} {}",
+            gutter, origin, self.span.line, self.span.column,
+            gutter,
+            self.span.line, line,
+            gutter, " ".repeat(offset), "^".repeat(length), self.label)
+    }
+}
@@ -0,0 +1,215 @@
+//!
+//! List encodings
+//!
+//! Lists are native to the reducer, but may instead be desugared into pure λ-terms before
+//! reduction, so that the same programs can be watched reducing as functions:
+//!
+//!     - Church lists are their own right fold: [a, b] is λc n. c a (c b n)
+//!     - Scott lists are their own case analysis: [a, b] is λn c. c a (λn c. c b (λn c. n))
+//!
+//! The empty list, cons and the list primitives are replaced by the terms below. The terms
+//! are written in source and alpha converted in the session, so that their binders are
+//! unique like any other. Church lists are folds already, so only their tail needs care;
+//! Scott lists recurse through fix. length stays built in, since it also takes strings,
+//! and the reducer unfolds it on an encoded list by the term of the encoding (see length).
+//!
+
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::ast::{
+    Constant,
+    Primitive
+};
+use crate::beta::NormalExpressionNode;
+use crate::environment::Environment;
+
+///
+/// List Encoding
+///
+///     Native  - lists are reduced as they are, with the list primitives as δ-rules
+///     Church  - lists are desugared into their right fold
+///     Scott   - lists are desugared into their case analysis
+///
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ListEncoding {
+    #[default]
+    Native,
+    Church,
+    Scott,
+}
+
+impl ListEncoding {
+    pub const ALL: [ListEncoding; 3] = [
+        ListEncoding::Native,
+        ListEncoding::Church,
+        ListEncoding::Scott,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ListEncoding::Native => "native",
+            ListEncoding::Church => "church",
+            ListEncoding::Scott => "scott",
+        }
+    }
+
+    // The source of the term standing for the empty list
+    fn nil(&self) -> &'static str {
+        match self {
+            ListEncoding::Native => "nil",
+            ListEncoding::Church => r"\c n. n",
+            ListEncoding::Scott => r"\n c. n",
+        }
+    }
+
+    // The source of the term standing for a list primitive
+    fn primitive(&self, primitive: Primitive) -> &'static str {
+        match (self, primitive) {
+            (ListEncoding::Church, Primitive::Cons) => r"\h t c n. c h (t c n)",
+            (ListEncoding::Church, Primitive::Head) => r"\l. l (\h r. h) (\c n. n)",
+            (ListEncoding::Church, Primitive::Tail) => r"\l c n. l (\h r g. g h (r c)) (\g. n) (\h t. t)",
+            (ListEncoding::Church, Primitive::IsNil) => r"\l. l (\h r. false) true",
+            (ListEncoding::Church, Primitive::Map) => r"\f l c n. l (\h r. c (f h) r) n",
+            (ListEncoding::Church, Primitive::Fold) => r"\f z l. l f z",
+            (ListEncoding::Scott, Primitive::Cons) => r"\h t n c. c h t",
+            (ListEncoding::Scott, Primitive::Head) => r"\l. l (\n c. n) (\h t. h)",
+            (ListEncoding::Scott, Primitive::Tail) => r"\l. l (\n c. n) (\h t. t)",
+            (ListEncoding::Scott, Primitive::IsNil) => r"\l. l true (\h t. false)",
+            (ListEncoding::Scott, Primitive::Map) => r"fix (\m f l. l (\n c. n) (\h t n c. c (f h) (m f t)))",
+            (ListEncoding::Scott, Primitive::Fold) => r"fix (\r f z l. l z (\h t. f h (r f z t)))",
+            _ => primitive.name(),
+        }
+    }
+
+    ///
+    /// The length of an encoded list, by one unfolding: l (λh r. r + 1) 0 folds a Church
+    /// list, and l 0 (λh t. 1 + length t) takes a Scott list apart, leaving the length of
+    /// the tail to the built-in length again. The terms are closed, so their binders
+    /// cannot capture.
+    /// 
+    pub fn length(&self, list: &Rc<NormalExpressionNode>) -> NormalExpressionNode {
+        let variable = |name: &str| Rc::new(NormalExpressionNode::Variable(name.to_string()));
+        let integer = |value| Rc::new(NormalExpressionNode::Constant(Constant::Integer(value)));
+        let binary = |name: &str, parameter: &str, body| {
+            Rc::new(NormalExpressionNode::Abstraction(name.to_string(), Rc::new(NormalExpressionNode::Abstraction(parameter.to_string(), Rc::new(body), false)), true))
+        };
+        let applied = |first, second| {
+            NormalExpressionNode::Application(Rc::new(NormalExpressionNode::Application(list.clone(), first)), second)
+        };
+
+        match self {
+            ListEncoding::Native => {
+                NormalExpressionNode::Application(Rc::new(NormalExpressionNode::Constant(Constant::Primitive(Primitive::Length))), list.clone())
+            },
+            ListEncoding::Church => {
+                applied(binary("h", "r", NormalExpressionNode::Arithmetic(variable("r"), String::from("+"), integer(1))), integer(0))
+            },
+            ListEncoding::Scott => {
+                let length = Rc::new(NormalExpressionNode::Constant(Constant::Primitive(Primitive::Length)));
+                let tail = NormalExpressionNode::Application(length, variable("t"));
+
+                applied(integer(0), binary("h", "t", NormalExpressionNode::Arithmetic(integer(1), String::from("+"), Rc::new(tail))))
+            },
+        }
+    }
+}
+
+impl fmt::Display for ListEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            ListEncoding::Native => "native lists",
+            ListEncoding::Church => "Church-encoded lists",
+            ListEncoding::Scott => "Scott-encoded lists",
+        };
+
+        f.pad(description)
+    }
+}
+
+impl FromStr for ListEncoding {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<ListEncoding, String> {
+        match name {
+            "native" => Ok(ListEncoding::Native),
+            "church" => Ok(ListEncoding::Church),
+            "scott" => Ok(ListEncoding::Scott),
+            _ => Err(format!("Unknown list encoding '{}'.", name)),
+        }
+    }
+}
+
+///
+/// Replace the lists in an expression by their encoding, alpha converting the terms
+/// introduced in the session environment
+///
+pub fn encode_lists(node: &NormalExpressionNode, encoding: ListEncoding, environment: &mut Environment) -> NormalExpressionNode {
+    match encoding {
+        ListEncoding::Native => node.clone(),
+        _ => encode(node, encoding, environment),
+    }
+}
+
+fn encode(node: &NormalExpressionNode, encoding: ListEncoding, environment: &mut Environment) -> NormalExpressionNode {
+    let mut encode_child = |child: &Rc<NormalExpressionNode>| Rc::new(encode(child.as_ref(), encoding, environment));
+
+    match node {
+        NormalExpressionNode::Variable(_) => {
+            node.clone()
+        },
+        NormalExpressionNode::Constant(Constant::Nil) => {
+            environment.term(encoding.nil())
+        },
+        NormalExpressionNode::Constant(Constant::Primitive(primitive)) if Primitive::LISTS.contains(primitive) && *primitive != Primitive::Length => {
+            environment.term(encoding.primitive(*primitive))
+        },
+        NormalExpressionNode::Constant(_) => {
+            node.clone()
+        },
+        NormalExpressionNode::Cons(head, tail) => {
+            let cons = Rc::new(environment.term(encoding.primitive(Primitive::Cons)));
+            let head = Rc::new(encode(head.as_ref(), encoding, environment));
+            let tail = Rc::new(encode(tail.as_ref(), encoding, environment));
+
+            NormalExpressionNode::Application(Rc::new(NormalExpressionNode::Application(cons, head)), tail)
+        },
+        NormalExpressionNode::Abstraction(parameter, body, curried) => {
+            NormalExpressionNode::Abstraction(parameter.clone(), encode_child(body), *curried)
+        },
+        NormalExpressionNode::Application(function, argument) => {
+            let function = encode_child(function);
+            NormalExpressionNode::Application(function, encode_child(argument))
+        },
+        NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+            let lhs = encode_child(lhs);
+            NormalExpressionNode::Arithmetic(lhs, operator.clone(), encode_child(rhs))
+        },
+        NormalExpressionNode::Let(parameter, expression, body, function) => {
+            let expression = encode_child(expression);
+            NormalExpressionNode::Let(parameter.clone(), expression, encode_child(body), *function)
+        },
+        NormalExpressionNode::LetRec(bindings, body) => {
+            let bindings = bindings.iter()
+                .map(|(parameter, expression, function)| (parameter.clone(), encode_child(expression), *function))
+                .collect();
+            NormalExpressionNode::LetRec(bindings, encode_child(body))
+        },
+        NormalExpressionNode::LetTuple(parameters, expression, body) => {
+            let expression = encode_child(expression);
+            NormalExpressionNode::LetTuple(parameters.clone(), expression, encode_child(body))
+        },
+        NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+            let condition = encode_child(condition);
+            let consequent = encode_child(consequent);
+            NormalExpressionNode::Conditional(condition, consequent, encode_child(alternative))
+        },
+        NormalExpressionNode::Tuple(elements) => {
+            NormalExpressionNode::Tuple(elements.iter().map(encode_child).collect())
+        },
+        NormalExpressionNode::Projection(tuple, index) => {
+            NormalExpressionNode::Projection(encode_child(tuple), *index)
+        },
+    }
+}
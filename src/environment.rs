@@ -0,0 +1,215 @@
+use std::rc::Rc;
+
+use crate::{
+    alpha::AlphaConverter,
+    ast::{ExpressionNode, Statement},
+    beta::NormalExpressionNode,
+    convert::to_normal_form,
+    lexer::Lexer,
+    parser::Parser,
+};
+
+///
+/// Session environment
+/// 
+/// Holds the top-level definitions made during a session, in the order they were made.
+/// Definitions are resolved when they are made, so a definition always refers to the 
+/// definitions that preceded it, even if these are later redefined or removed.
+/// 
+/// Every expression in the session is alpha converted by the same converter, so binders
+/// are unique across definitions and substituting one into another cannot capture.
+/// 
+pub struct Environment {
+    definitions: Vec<(String, NormalExpressionNode)>,
+    alpha_converter: AlphaConverter,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment {
+            definitions: Vec::new(),
+            alpha_converter: AlphaConverter::new(),
+        }
+    }
+
+    pub fn alpha_convert(&mut self, expression: &ExpressionNode) {
+        let _ = self.alpha_converter.convert(expression);
+    }
+
+    ///
+    /// Read a term from source known to be a well-formed expression, such as that of a list
+    /// encoding, alpha converted like any other in the session
+    /// 
+    pub fn term(&mut self, source: &str) -> NormalExpressionNode {
+        let token_list = Lexer::new(source.to_string())
+            .scan()
+            .unwrap_or_else(|err| panic!("`{}` is well formed: {}", source, err));
+
+        match Parser::new(token_list).parse_statement() {
+            Ok(Statement::Expression(ast)) => {
+                self.alpha_convert(&ast);
+                to_normal_form(&ast)
+            },
+            _ => panic!("`{}` is an expression", source),
+        }
+    }
+
+    // An expression with its binders named as the user wrote them
+    pub fn restore_names(&self, node: &NormalExpressionNode) -> NormalExpressionNode {
+        self.alpha_converter.restore(node)
+    }
+
+    pub fn definitions(&self) -> &[(String, NormalExpressionNode)] {
+        &self.definitions
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&NormalExpressionNode> {
+        self.definitions.iter()
+            .find(|(defined, _)| defined == name)
+            .map(|(_, value)| value)
+    }
+
+    // Bind a name to a value, resolving the definitions the value refers to
+    pub fn define(&mut self, name: &str, value: NormalExpressionNode) {
+        let value = self.resolve(&value);
+
+        self.undefine(name);
+        self.definitions.push((name.to_string(), value));
+    }
+
+    pub fn undefine(&mut self, name: &str) -> bool {
+        let count = self.definitions.len();
+
+        self.definitions.retain(|(defined, _)| defined != name);
+        
+        self.definitions.len() != count
+    }
+
+    pub fn clear(&mut self) {
+        self.definitions.clear();
+    }
+
+    ///
+    /// Replace the free variables of an expression that name a definition with its value
+    /// 
+    pub fn resolve(&self, node: &NormalExpressionNode) -> NormalExpressionNode {
+        self.resolve_within(node, &mut Vec::new())
+    }
+
+    fn resolve_within(&self, node: &NormalExpressionNode, bound: &mut Vec<String>) -> NormalExpressionNode {
+        match node {
+            NormalExpressionNode::Variable(name) => {
+                match self.lookup(name) {
+                    Some(value) if !bound.contains(name) => value.clone(),
+                    _ => node.clone()
+                }
+            },
+            NormalExpressionNode::Constant(_) => {
+                node.clone()
+            },
+            NormalExpressionNode::Abstraction(parameter, body, curried) => {
+                bound.push(parameter.clone());
+                let body = self.resolve_within(body.as_ref(), bound);
+                bound.pop();
+
+                NormalExpressionNode::Abstraction(parameter.clone(), Rc::new(body), *curried)
+            },
+            NormalExpressionNode::Application(function, argument) => {
+                NormalExpressionNode::Application(
+                    Rc::new(self.resolve_within(function.as_ref(), bound)),
+                    Rc::new(self.resolve_within(argument.as_ref(), bound)))
+            },
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
+                NormalExpressionNode::Arithmetic(
+                    Rc::new(self.resolve_within(lhs.as_ref(), bound)),
+                    operator.clone(),
+                    Rc::new(self.resolve_within(rhs.as_ref(), bound)))
+            },
+            NormalExpressionNode::Let(parameter, expression, body, function) => {
+                let expression = self.resolve_within(expression.as_ref(), bound);
+
+                bound.push(parameter.clone());
+                let body = self.resolve_within(body.as_ref(), bound);
+                bound.pop();
+
+                NormalExpressionNode::Let(parameter.clone(), Rc::new(expression), Rc::new(body), *function)
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                bound.extend(bindings.iter().map(|(parameter, _, _)| parameter.clone()));
+
+                let resolved = bindings.iter()
+                    .map(|(parameter, expression, function)| (parameter.clone(), Rc::new(self.resolve_within(expression.as_ref(), bound)), *function))
+                    .collect();
+                let body = self.resolve_within(body.as_ref(), bound);
+
+                bound.truncate(bound.len() - bindings.len());
+
+                NormalExpressionNode::LetRec(resolved, Rc::new(body))
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                NormalExpressionNode::Conditional(
+                    Rc::new(self.resolve_within(condition.as_ref(), bound)),
+                    Rc::new(self.resolve_within(consequent.as_ref(), bound)),
+                    Rc::new(self.resolve_within(alternative.as_ref(), bound)))
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                let expression = self.resolve_within(expression.as_ref(), bound);
+
+                bound.extend(parameters.iter().cloned());
+                let body = self.resolve_within(body.as_ref(), bound);
+                bound.truncate(bound.len() - parameters.len());
+
+                NormalExpressionNode::LetTuple(parameters.clone(), Rc::new(expression), Rc::new(body))
+            },
+            NormalExpressionNode::Tuple(elements) => {
+                NormalExpressionNode::Tuple(elements.iter()
+                    .map(|element| Rc::new(self.resolve_within(element.as_ref(), bound)))
+                    .collect())
+            },
+            NormalExpressionNode::Projection(tuple, index) => {
+                NormalExpressionNode::Projection(Rc::new(self.resolve_within(tuple.as_ref(), bound)), *index)
+            },
+            NormalExpressionNode::Cons(head, tail) => {
+                NormalExpressionNode::Cons(
+                    Rc::new(self.resolve_within(head.as_ref(), bound)),
+                    Rc::new(self.resolve_within(tail.as_ref(), bound)))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Constant;
+
+    fn variable(name: &str) -> NormalExpressionNode {
+        NormalExpressionNode::Variable(name.to_string())
+    }
+
+    fn successor(node: NormalExpressionNode) -> NormalExpressionNode {
+        NormalExpressionNode::Arithmetic(Rc::new(node), "+".to_string(), Rc::new(NormalExpressionNode::Constant(Constant::Integer(1))))
+    }
+
+    #[test]
+    fn redefinition_refers_to_the_previous_value() {
+        let mut environment = Environment::new();
+
+        environment.define("z", successor(variable("z")));
+        environment.define("z", successor(variable("z")));
+
+        assert_eq!(environment.lookup("z"), Some(&successor(successor(variable("z")))));
+    }
+
+    #[test]
+    fn forward_references_stay_free() {
+        let mut environment = Environment::new();
+
+        environment.define("w", variable("q"));
+        environment.define("q", NormalExpressionNode::Constant(Constant::Integer(5)));
+        environment.define("r", variable("w"));
+
+        assert_eq!(environment.lookup("w"), Some(&variable("q")));
+        assert_eq!(environment.lookup("r"), Some(&variable("q")));
+    }
+}
@@ -0,0 +1,106 @@
+//!
+//! Equivalence of expressions
+//!
+//! Two expressions are α-equivalent when they differ only in the names of bound variables,
+//! and βη-equivalent (convertible) when they reduce to α-equivalent βη-normal forms.
+//! Convertibility is undecidable in general: expressions that do not normalise within the
+//! fuel given cannot be compared.
+//!
+
+use crate::beta::{
+    BetaReducer,
+    Fuel,
+    NormalExpressionNode,
+    ReductionStrategy
+};
+use crate::encoding::ListEncoding;
+use crate::error::AlabastaError;
+
+///
+/// Equivalence
+///
+///     Alpha     - the expressions are α-equivalent as they stand
+///     BetaEta   - the expressions reduce to the same βη-normal form, held here
+///     Distinct  - the expressions have different βη-normal forms, held here
+///
+#[derive(Debug, Clone)]
+pub enum Equivalence {
+    Alpha,
+    BetaEta(NormalExpressionNode),
+    Distinct(NormalExpressionNode, NormalExpressionNode),
+}
+
+impl Equivalence {
+    pub fn holds(&self) -> bool {
+        !matches!(self, Equivalence::Distinct(_, _))
+    }
+}
+
+///
+/// Whether two expressions differ only in the names of their bound variables
+///
+pub fn alpha_equivalent(lhs: &NormalExpressionNode, rhs: &NormalExpressionNode) -> bool {
+    // Expressions compare up to the names of their binders (see NormalExpressionNode)
+    lhs == rhs
+}
+
+///
+/// Decide whether two expressions are α- or βη-equivalent. Both are reduced in normal order,
+/// which finds a normal form whenever one exists, within the given fuel, with lists in the
+/// encoding they were desugared into.
+///
+pub fn equivalence(lhs: &NormalExpressionNode, rhs: &NormalExpressionNode, fuel: Fuel, lists: ListEncoding) -> Result<Equivalence, AlabastaError> {
+    if alpha_equivalent(lhs, rhs) {
+        return Ok(Equivalence::Alpha);
+    }
+
+    let mut beta_reducer = BetaReducer::new(ReductionStrategy::NormalOrder);
+    beta_reducer.set_fuel(fuel);
+    beta_reducer.set_eta(true);
+    beta_reducer.set_lists(lists);
+
+    let lhs = beta_reducer.reduce(lhs)?;
+    let rhs = beta_reducer.reduce(rhs)?;
+
+    if alpha_equivalent(&lhs, &rhs) {
+        Ok(Equivalence::BetaEta(lhs))
+    } else {
+        Ok(Equivalence::Distinct(lhs, rhs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+
+    fn outcome(lhs: &str, rhs: &str) -> Equivalence {
+        let mut environment = Environment::new();
+        let (lhs, rhs) = (environment.term(lhs), environment.term(rhs));
+
+        equivalence(&lhs, &rhs, Fuel::default(), ListEncoding::Native).unwrap()
+    }
+
+    #[test]
+    fn equivalence_is_alpha_then_beta_eta() {
+        assert!(matches!(outcome(r"\x y. x", r"\a b. a"), Equivalence::Alpha));
+        assert!(matches!(outcome(r"(\x. x) 1", "1"), Equivalence::BetaEta(_)));
+        assert!(matches!(outcome(r"\x y. x", r"\x y. y"), Equivalence::Distinct(_, _)));
+    }
+
+    #[test]
+    fn eta_reduction_makes_an_abstraction_equivalent_to_its_function() {
+        assert!(matches!(outcome(r"\x. f x", "f"), Equivalence::BetaEta(_)));
+        assert!(matches!(outcome(r"\x y. f x y", "f"), Equivalence::BetaEta(_)));
+        assert!(matches!(outcome(r"\x. f x x", "f"), Equivalence::Distinct(_, _)));
+    }
+
+    #[test]
+    fn let_rec_is_equivalent_to_its_fixpoint() {
+        let recursive = r"let rec f n = if n == 0 then 0 else f (n - 1) in \y. f 3";
+
+        assert!(matches!(outcome(recursive, r"\y. fix (\f n. if n == 0 then 0 else f (n - 1)) 3"), Equivalence::BetaEta(_)));
+        assert!(matches!(outcome(recursive, r"\y. 0"), Equivalence::BetaEta(_)));
+        assert!(matches!(outcome(recursive, r"\y. 1"), Equivalence::Distinct(_, _)));
+    }
+}
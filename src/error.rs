@@ -0,0 +1,129 @@
+//!
+//! Errors reported by every stage of the pipeline, from lexing to reduction
+//!
+
+use std::fmt;
+
+use crate::beta::Divergence;
+use crate::diagnostic::{
+    Diagnostic,
+    ErrorCode
+};
+
+///
+/// Arithmetic Error
+///
+/// A δ-rule that cannot be applied, holding its operands.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArithmeticError {
+    DivisionByZero { lhs: i64, operator: String },
+    Overflow { lhs: i64, operator: String, rhs: i64 },
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArithmeticError::DivisionByZero { lhs, operator } => {
+                write!(f, "division by zero in `{} {} 0`", lhs, operator)
+            },
+            // Subtracting from zero is how unary minus reads (see parser)
+            ArithmeticError::Overflow { lhs: 0, operator, rhs } if operator == "-" => {
+                write!(f, "integer overflow in `-({})`", rhs)
+            },
+            ArithmeticError::Overflow { lhs, operator, rhs } => {
+                write!(f, "integer overflow in `{} {} {}`", lhs, operator, rhs)
+            },
+        }
+    }
+}
+
+///
+/// Alabasta Error
+///
+///     Lex            - the input contains something other than a token
+///     Parse          - the tokens do not form a statement
+///     Unbound        - a name is used that has not been defined
+///     Arithmetic     - an arithmetic operator cannot be applied to its operands
+///     Type           - a term is used in a way its value does not allow, such as applying a constant
+///     ResourceLimit  - the expression did not normalise within the fuel given
+///     Io             - a file or the terminal could not be read
+///     Multiple       - several errors found in the same input, reported together
+///
+#[derive(Debug, Clone)]
+pub enum AlabastaError {
+    Lex(Diagnostic),
+    Parse(Diagnostic),
+    Unbound(String),
+    Arithmetic(ArithmeticError),
+    Type(String),
+    ResourceLimit(Divergence),
+    Io(String),
+    Multiple(Vec<AlabastaError>),
+}
+
+impl AlabastaError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AlabastaError::Lex(diagnostic) | AlabastaError::Parse(diagnostic) => diagnostic.code,
+            AlabastaError::Unbound(_) => ErrorCode::Unbound,
+            AlabastaError::Arithmetic(ArithmeticError::DivisionByZero { .. }) => ErrorCode::DivisionByZero,
+            AlabastaError::Arithmetic(ArithmeticError::Overflow { .. }) => ErrorCode::Overflow,
+            AlabastaError::Type(_) => ErrorCode::TypeMismatch,
+            AlabastaError::ResourceLimit(_) => ErrorCode::ResourceLimit,
+            AlabastaError::Io(_) => ErrorCode::Io,
+            AlabastaError::Multiple(errors) => errors.first().map_or(ErrorCode::UnexpectedToken, AlabastaError::code),
+        }
+    }
+
+    ///
+    /// Attach the source an error was found in, so that lexical and syntax errors
+    /// display the offending line (see Diagnostic::located)
+    ///
+    pub fn located(self, source: &str, origin: &str) -> AlabastaError {
+        match self {
+            AlabastaError::Lex(diagnostic) => AlabastaError::Lex(diagnostic.located(source, origin)),
+            AlabastaError::Parse(diagnostic) => AlabastaError::Parse(diagnostic.located(source, origin)),
+            AlabastaError::Multiple(errors) => {
+                AlabastaError::Multiple(errors.into_iter().map(|error| error.located(source, origin)).collect())
+            },
+            error => error
+        }
+    }
+}
+
+impl fmt::Display for AlabastaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlabastaError::Lex(diagnostic) | AlabastaError::Parse(diagnostic) => {
+                write!(f, "{}", diagnostic)
+            },
+            AlabastaError::Unbound(name) => {
+                write!(f, "[{}] `{}` is not defined", self.code(), name)
+            },
+            AlabastaError::Arithmetic(error) => {
+                write!(f, "[{}] {}", self.code(), error)
+            },
+            AlabastaError::Type(message) => {
+                write!(f, "[{}] {}", self.code(), message)
+            },
+            AlabastaError::ResourceLimit(divergence) => {
+                write!(f, "[{}] {}", self.code(), divergence)
+            },
+            AlabastaError::Io(message) => {
+                write!(f, "[{}] {}", self.code(), message)
+            },
+            AlabastaError::Multiple(errors) => {
+                write!(f, "{} errors", errors.len())?;
+
+                for error in errors {
+                    write!(f, "\n{}", error)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for AlabastaError {}
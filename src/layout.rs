@@ -0,0 +1,183 @@
+//!
+//! Layout
+//!
+//! A pretty printing engine after Wadler's "A prettier printer". A document is text with
+//! optional line breaks; a group of breaks is laid out on one line when it fits in the
+//! width remaining, and otherwise every break in the group becomes a newline, indented
+//! by the nesting in force at the break.
+//!
+
+use std::rc::Rc;
+
+///
+/// Document
+///
+///     Nil     - nothing
+///     Text    - text without newlines
+///     Escape  - text taking no room on the terminal, such as a colour escape code
+///     Line    - a space, or a newline when its group is broken
+///     Concat  - one document followed by another
+///     Nest    - a document whose newlines are indented further
+///     Group   - a document laid out on one line if it fits, with its width on one line
+///
+#[derive(Debug, Clone)]
+pub enum Doc {
+    Nil,
+    Text(String),
+    Escape(String),
+    Line,
+    Concat(Rc<Doc>, Rc<Doc>),
+    Nest(usize, Rc<Doc>),
+    Group(usize, Rc<Doc>),
+}
+
+impl Doc {
+    pub fn text(text: &str) -> Doc {
+        Doc::Text(text.to_string())
+    }
+
+    pub fn append(self, other: Doc) -> Doc {
+        match (self, other) {
+            (Doc::Nil, other) => other,
+            (this, Doc::Nil) => this,
+            (this, other) => Doc::Concat(Rc::new(this), Rc::new(other)),
+        }
+    }
+
+    pub fn nest(self, indent: usize) -> Doc {
+        Doc::Nest(indent, Rc::new(self))
+    }
+
+    pub fn group(self) -> Doc {
+        Doc::Group(self.flat_width(), Rc::new(self))
+    }
+
+    // The width of a document laid out on one line. Groups know theirs, so that nested
+    // groups are measured once rather than once for every group around them.
+    fn flat_width(&self) -> usize {
+        let mut width = 0;
+        let mut pending = vec![self];
+
+        while let Some(doc) = pending.pop() {
+            match doc {
+                Doc::Nil | Doc::Escape(_) => { },
+                Doc::Text(text) => width += text.chars().count(),
+                Doc::Line => width += 1,
+                Doc::Concat(first, second) => {
+                    pending.push(second.as_ref());
+                    pending.push(first.as_ref());
+                },
+                Doc::Nest(_, doc) => pending.push(doc.as_ref()),
+                Doc::Group(group_width, _) => width += group_width,
+            }
+        }
+
+        width
+    }
+
+    ///
+    /// Lay a document out in the given width, starting at the given column. Lines after
+    /// the first are indented to that column, so that a document can follow a prompt.
+    ///
+    pub fn pretty(&self, width: usize, column: usize) -> String {
+        let mut out = String::new();
+        let mut position = column;
+        let mut stack = vec![(column, Mode::Break, self)];
+
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Nil => { },
+                Doc::Text(text) => {
+                    out.push_str(text);
+                    position += text.chars().count();
+                },
+                Doc::Escape(text) => {
+                    out.push_str(text);
+                },
+                Doc::Line => {
+                    match mode {
+                        Mode::Flat => {
+                            out.push(' ');
+                            position += 1;
+                        },
+                        Mode::Break => {
+                            out.push('\n');
+                            out.push_str(&" ".repeat(indent));
+                            position = indent;
+                        },
+                    }
+                },
+                Doc::Concat(first, second) => {
+                    stack.push((indent, mode, second.as_ref()));
+                    stack.push((indent, mode, first.as_ref()));
+                },
+                Doc::Nest(nesting, doc) => {
+                    stack.push((indent + nesting, mode, doc.as_ref()));
+                },
+                Doc::Group(_, group) => {
+                    let mode = match mode {
+                        Mode::Flat => Mode::Flat,
+                        Mode::Break if fits(width.saturating_sub(position), doc, &stack) => Mode::Flat,
+                        Mode::Break => Mode::Break,
+                    };
+
+                    stack.push((indent, mode, group.as_ref()));
+                },
+            }
+        }
+
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+// Whether a group laid out flat, and whatever follows it up to the next newline, fits in the room left
+fn fits(room: usize, group: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    // Unlimited room, as in Display, is more than any document needs
+    let mut room = isize::try_from(room).unwrap_or(isize::MAX);
+    let mut pending = vec![(Mode::Flat, group)];
+    let mut rest = rest.iter().rev();
+
+    loop {
+        if room < 0 {
+            return false;
+        }
+
+        let (mode, doc) = match pending.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+
+        match doc {
+            Doc::Nil | Doc::Escape(_) => { },
+            Doc::Text(text) => {
+                room -= text.chars().count() as isize;
+            },
+            Doc::Line => {
+                match mode {
+                    Mode::Flat => room -= 1,
+                    Mode::Break => return true,
+                }
+            },
+            Doc::Concat(first, second) => {
+                pending.push((mode, second.as_ref()));
+                pending.push((mode, first.as_ref()));
+            },
+            // A group inside one laid out flat is flat too
+            Doc::Group(width, _) if mode == Mode::Flat => {
+                room -= *width as isize;
+            },
+            Doc::Nest(_, doc) | Doc::Group(_, doc) => {
+                pending.push((mode, doc.as_ref()));
+            },
+        }
+    }
+}
@@ -1,68 +1,220 @@
-///
-/// Lexical analysis (tokenization)
-/// 
-/// The lexer takes a string of characters and converts it into a list of tokens.
-/// 
+//!
+//! Lexical analysis (tokenization)
+//! 
+//! The lexer takes a string of characters and converts it into a list of tokens. Input is
+//! read a character at a time, so positions, spans and columns count characters, not bytes.
+//! 
+//! Comments are either line comments, from `--` to the end of the line, or block comments
+//! between `{-` and `-}`, which may be nested. They are not tokens, but are kept as trivia
+//! on the token that follows them (see Token).
+//! 
+//! String literals are written between double quotes, "…", and character literals between
+//! single quotes, 'c', on one line. Within them a backslash escapes a newline (\n), a tab
+//! (\t), a carriage return (\r), the null character (\0), a backslash (\\), either quote
+//! (\" and \') or any character by its code point in hexadecimal (\u{3bb}). As primes may 
+//! end identifiers, only a single quote where a token starts begins a character literal.
+//! 
+//! Identifiers start with a letter, in any script, and continue with letters, digits, 
+//! underscores and primes. Abstractions may be written with `\`, `^` or `λ`, and their
+//! body introduced with `.` or `->`. The keywords `let`, `rec`, `and`, `in`, `def`, `true`,
+//! `false`, `if`, `then`, `else`, `not`, `fix`, `fst`, `snd`, `nil`, `cons`, `head`, `tail`, 
+//! `isnil`, `map`, `fold`, `length`, `concat`, `charAt` and `show` cannot be used as 
+//! identifiers.
+//! 
+
+use std::fmt;
+
+use crate::diagnostic::{
+    Diagnostic,
+    ErrorCode,
+    Span
+};
+use crate::ast::{
+    Constant,
+    Primitive
+};
+use crate::error::AlabastaError;
 
 #[derive(Debug, PartialEq)]
 pub struct Lexer {
-    input: String,
+    input: Vec<char>,
     position: usize,
+    line: usize,
+    line_start: usize,
+    // Comments read since the last token
+    trivia: Vec<Trivia>,
 }
 
 impl Lexer {
     pub fn new(input: String) -> Lexer {
         Lexer {
-            input,
+            input: input.chars().collect(),
             position: 0,
+            line: 1,
+            line_start: 0,
+            trivia: Vec::new(),
         }
     }
 
+    ///
+    /// Comments following the last token, which have no token to be kept with. Like the
+    /// trivia of tokens, they are kept for a formatter to put back.
+    /// 
+    #[allow(dead_code)]
+    pub fn trailing_trivia(&self) -> &[Trivia] {
+        &self.trivia
+    }
+
+    // The text from a position up to the current one
+    fn text_from(&self, start: usize) -> String {
+        self.input[start..self.position].iter().collect()
+    }
+
     fn position(&self) -> usize {
         self.position
     }
 
+    fn column(&self) -> usize {
+        self.position - self.line_start + 1
+    }
+
     fn next(&mut self) -> Option<char> {
         let chr = self.peek();
         
         self.position += 1;
 
+        if chr == Some('\n') {
+            self.line += 1;
+            self.line_start = self.position;
+        }
+
         chr
     }
 
+    fn peek_second(&self) -> Option<char> {
+        self.input.get(self.position + 1).copied()
+    }
+
+    // The next character, or '\0' at the end of input
     fn peek(&mut self) -> Option<char> {
-        if self.position >= self.input.len() {
-            return Some('\0');
-        }
+        Some(self.input.get(self.position).copied().unwrap_or('\0'))
+    }
 
-        if let Some(chr_as_str) = self.input.get(self.position..self.position+1) {
-            return chr_as_str.chars().next();
-        }
+    // The comparison, logical or cons operator starting at the current position, if any
+    fn operator(&self) -> Option<&'static str> {
+        let operator = match (self.input.get(self.position), self.peek_second()) {
+            (Some('='), Some('=')) => "==",
+            (Some('!'), Some('=')) => "!=",
+            (Some('<'), Some('=')) => "<=",
+            (Some('>'), Some('=')) => ">=",
+            (Some('&'), Some('&')) => "&&",
+            (Some('|'), Some('|')) => "||",
+            (Some(':'), Some(':')) => "::",
+            (Some('<'), _) => "<",
+            (Some('>'), _) => ">",
+            _ => return None,
+        };
+
+        Some(operator)
+    }
+
+    ///
+    /// Read a literal between quotes, from its opening quote, resolving escapes
+    /// 
+    fn quoted(&mut self, quote: char) -> Result<String, AlabastaError> {
+        let start = self.position;
+        let column = self.column();
+        let mut text = String::new();
+
+        self.next();
+
+        loop {
+            let escape_position = self.position;
+
+            match self.peek() {
+                Some(chr) if chr == quote => {
+                    self.next();
+                    return Ok(text);
+                },
+                Some('\n' | '\0') | None => {
+                    return Err(AlabastaError::Lex(Diagnostic::new(
+                        ErrorCode::UnterminatedLiteral,
+                        format!("unterminated {} literal", if quote == '"' { "string" } else { "character" }),
+                        format!("never closed with `{}`", quote),
+                        Span::new(start, start + 1, self.line, column))));
+                },
+                Some('\\') => {
+                    self.next();
+
+                    let escaped = match self.peek() {
+                        Some('n') => Some('\n'),
+                        Some('t') => Some('\t'),
+                        Some('r') => Some('\r'),
+                        Some('0') => Some('\0'),
+                        Some(chr @ ('\\' | '"' | '\'')) => Some(chr),
+                        Some('u') if self.peek_second() == Some('{') => {
+                            self.next();
+                            self.next();
+
+                            let mut digits = String::new();
+
+                            while let Some(chr) = self.peek().filter(char::is_ascii_hexdigit) {
+                                digits.push(chr);
+                                self.next();
+                            }
 
-        return Some('\0');
+                            match self.peek() {
+                                Some('}') => u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32),
+                                _ => None,
+                            }
+                        },
+                        _ => None,
+                    };
+
+                    let Some(escaped) = escaped else {
+                        return Err(AlabastaError::Lex(Diagnostic::new(
+                            ErrorCode::InvalidLiteral,
+                            "invalid escape sequence".to_string(),
+                            "expected one of \\n \\t \\r \\0 \\\\ \\\" \\' or \\u{…}".to_string(),
+                            Span::new(escape_position, self.position + 1, self.line, escape_position - self.line_start + 1))));
+                    };
+
+                    text.push(escaped);
+                    self.next();
+                },
+                Some(chr) => {
+                    text.push(chr);
+                    self.next();
+                }
+            }
+        }
     }
 
-    pub fn scan(&mut self) -> Result<Vec::<Token>, String> {
+    pub fn scan(&mut self) -> Result<Vec::<Token>, AlabastaError> {
         let mut symbol;
         let mut symbol_position;
+        let mut symbol_column;
+        let mut symbol_line;
         let mut token_list = Vec::<Token>::new();
 
         loop {
             symbol = self.peek().unwrap();
             symbol_position = self.position;
+            symbol_column = self.column();
+            symbol_line = self.line;
 
             match symbol
             {
                 // Whitespace
-                ' ' | '\t' | '\n' => {
+                ' ' | '\t' | '\r' | '\n' => {
                     self.next();
                 },
                 // Identifier
-                'a'..='z' | 'A'..='Z' => {
+                chr if chr.is_alphabetic() && chr != 'λ' => {
                     let mut identifier = String::new();
 
                     while let Some(chr) = self.peek() {
-                        if chr.is_alphanumeric() || chr == '_' {
+                        if (chr.is_alphanumeric() && chr != 'λ') || chr == '_' || chr == '\'' {
                             identifier.push(chr);
                             self.next();
                         } else {
@@ -70,11 +222,21 @@ impl Lexer {
                         }
                     }
 
+                    let Some(token_type) = lexeme_from_string(identifier.clone()) else {
+                        return Err(AlabastaError::Lex(Diagnostic::new(
+                            ErrorCode::UnexpectedCharacter,
+                            format!("invalid identifier `{}`", identifier),
+                            "identifiers may contain only letters, digits, underscores and primes".to_string(),
+                            Span::new(symbol_position, self.position(), self.line, symbol_column))));
+                    };
+
                     token_list.push(Token {
-                        token_type: lexeme_from_string(identifier),
-                        line_number: 0,
+                        token_type,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // Integer
@@ -82,7 +244,7 @@ impl Lexer {
                     let mut integer = String::new();
 
                     while let Some(chr) = self.peek() {
-                        if chr.is_numeric() {
+                        if chr.is_ascii_digit() {
                             integer.push(chr);
                             self.next();
                         } else {
@@ -90,33 +252,142 @@ impl Lexer {
                         }
                     }
 
+                    let Ok(value) = integer.parse::<i64>() else {
+                        return Err(AlabastaError::Lex(Diagnostic::new(
+                            ErrorCode::IntegerTooLarge,
+                            format!("integer literal `{}` is too large", integer),
+                            format!("does not fit in {} bits", i64::BITS),
+                            Span::new(symbol_position, self.position(), self.line, symbol_column))));
+                    };
+
+                    token_list.push(Token {
+                        token_type: Lexeme::Integer(value),
+                        line_number: self.line,
+                        column: symbol_column,
+                        char_start: symbol_position,
+                        char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
+                    });
+                },
+                // String
+                '"' => {
+                    let text = self.quoted('"')?;
+
+                    token_list.push(Token {
+                        token_type: Lexeme::String(text),
+                        line_number: self.line,
+                        column: symbol_column,
+                        char_start: symbol_position,
+                        char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
+                    });
+                },
+                // Character, a single one between quotes
+                '\'' => {
+                    let text = self.quoted('\'')?;
+                    let mut chars = text.chars();
+
+                    let (Some(chr), None) = (chars.next(), chars.next()) else {
+                        return Err(AlabastaError::Lex(Diagnostic::new(
+                            ErrorCode::InvalidLiteral,
+                            "character literal must hold one character".to_string(),
+                            match text.is_empty() {
+                                true => "is empty".to_string(),
+                                false => format!("holds {} characters; use double quotes for a string", text.chars().count()),
+                            },
+                            Span::new(symbol_position, self.position(), self.line, symbol_column))));
+                    };
+
+                    token_list.push(Token {
+                        token_type: Lexeme::Character(chr),
+                        line_number: self.line,
+                        column: symbol_column,
+                        char_start: symbol_position,
+                        char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
+                    });
+                },
+                // Arrow, introducing the body of an abstraction
+                '-' if self.peek_second() == Some('>') => {
+                    self.next();
+                    self.next();
+
                     token_list.push(Token {
-                        token_type: Lexeme::Integer(integer.parse::<i64>().unwrap()),
-                        line_number: 0,
+                        token_type: Lexeme::Arrow,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
+                // Line comment, up to the end of the line
+                '-' if self.peek_second() == Some('-') => {
+                    while self.position < self.input.len() && self.peek() != Some('\n') {
+                        self.next();
+                    }
+
+                    self.trivia.push(Trivia::LineComment(self.text_from(symbol_position)));
+                },
+                // Block comment, which may contain further block comments
+                '{' if self.peek_second() == Some('-') => {
+                    let mut depth = 0;
+
+                    loop {
+                        match (self.peek(), self.peek_second()) {
+                            (Some('{'), Some('-')) => {
+                                depth += 1;
+                                self.next();
+                                self.next();
+                            },
+                            (Some('-'), Some('}')) => {
+                                depth -= 1;
+                                self.next();
+                                self.next();
+
+                                if depth == 0 {
+                                    break;
+                                }
+                            },
+                            _ if self.position >= self.input.len() => {
+                                return Err(AlabastaError::Lex(Diagnostic::new(
+                                    ErrorCode::UnterminatedComment,
+                                    "unterminated block comment".to_string(),
+                                    "never closed with `-}`".to_string(),
+                                    Span::new(symbol_position, symbol_position + 2, symbol_line, symbol_column))));
+                            },
+                            _ => {
+                                self.next();
+                            }
+                        }
+                    }
+
+                    self.trivia.push(Trivia::BlockComment(self.text_from(symbol_position)));
+                },
                 // Binary Operator
                 '+' | '-' | '*' | '/' | '%' => {                    
                     self.next();
 
                     token_list.push(Token {
                         token_type: Lexeme::BinaryOperator(symbol.to_string()),
-                        line_number: 0,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // Lambda
-                '\\' | '^' => {
+                '\\' | '^' | 'λ' => {
                     self.next();
 
                     token_list.push(Token {
                         token_type: Lexeme::Lambda,
-                        line_number: 0,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // Dot
@@ -125,9 +396,11 @@ impl Lexer {
 
                     token_list.push(Token {
                         token_type: Lexeme::Dot,
-                        line_number: 0,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // Left Paren
@@ -136,9 +409,11 @@ impl Lexer {
 
                     token_list.push(Token {
                         token_type: Lexeme::LeftParen,
-                        line_number: 0,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // Right Paren
@@ -147,9 +422,37 @@ impl Lexer {
 
                     token_list.push(Token {
                         token_type: Lexeme::RightParen,
-                        line_number: 0,
+                        line_number: self.line,
+                        column: symbol_column,
+                        char_start: symbol_position,
+                        char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
+                    });
+                },
+                // Left Bracket
+                '[' => {
+                    self.next();
+
+                    token_list.push(Token {
+                        token_type: Lexeme::LeftBracket,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
+                    });
+                },
+                // Right Bracket
+                ']' => {
+                    self.next();
+
+                    token_list.push(Token {
+                        token_type: Lexeme::RightBracket,
+                        line_number: self.line,
+                        column: symbol_column,
+                        char_start: symbol_position,
+                        char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // Comma
@@ -158,9 +461,28 @@ impl Lexer {
 
                     token_list.push(Token {
                         token_type: Lexeme::Comma,
-                        line_number: 0,
+                        line_number: self.line,
+                        column: symbol_column,
+                        char_start: symbol_position,
+                        char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
+                    });
+                },
+                // Comparison, logical and cons operators
+                '=' | '!' | '<' | '>' | '&' | '|' | ':' if self.operator().is_some() => {
+                    let operator = self.operator().unwrap_or_default();
+
+                    for _ in operator.chars() {
+                        self.next();
+                    }
+
+                    token_list.push(Token {
+                        token_type: Lexeme::BinaryOperator(operator.to_string()),
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // Equals
@@ -169,9 +491,11 @@ impl Lexer {
 
                     token_list.push(Token {
                         token_type: Lexeme::Equals,
-                        line_number: 0,
+                        line_number: self.line,
+                        column: symbol_column,
                         char_start: symbol_position,
                         char_end: self.position(),
+                        trivia: std::mem::take(&mut self.trivia),
                     });
                 },
                 // End of input
@@ -179,7 +503,11 @@ impl Lexer {
                     return Ok(token_list);
                 },
                 _ => {
-                    return Err("Unexpected symbol encountered!".to_string());
+                    return Err(AlabastaError::Lex(Diagnostic::new(
+                        ErrorCode::UnexpectedCharacter,
+                        format!("unexpected character `{}`", symbol),
+                        "not recognised".to_string(),
+                        Span::new(symbol_position, symbol_position + 1, self.line, symbol_column))));
                 }
             }
 
@@ -197,44 +525,168 @@ pub enum Lexeme
 {
     Identifier(String),
     Integer(i64),
+    Boolean(bool),
+    String(String),
+    Character(char),
+    Nil,
+    Primitive(Primitive),
     BinaryOperator(String),
     Let,
+    Rec,
+    And,
     In,
+    If,
+    Then,
+    Else,
+    Not,
+    Def,
     Equals,
     Lambda,
     Dot,
+    Arrow,
     LeftParen,
     RightParen,
+    LeftBracket,
+    RightBracket,
     Comma,
 }
 
-pub fn lexeme_from_string(input: String) -> Lexeme {
-    match input.as_str() {
+///
+/// The lexeme a string of characters denotes, if any
+/// 
+pub fn lexeme_from_string(input: String) -> Option<Lexeme> {
+    let lexeme = match input.as_str() {
         "+" | "-" | "*" | "/" | "%" => Lexeme::BinaryOperator(input),
-        "\\" | "^"=> Lexeme::Lambda,
+        "==" | "!=" | "<" | "<=" | ">" | ">=" | "&&" | "||" | "::" => Lexeme::BinaryOperator(input),
+        "\\" | "^" | "λ" => Lexeme::Lambda,
         "." => Lexeme::Dot,
+        "->" => Lexeme::Arrow,
         "(" => Lexeme::LeftParen,
         ")" => Lexeme::RightParen,
+        "[" => Lexeme::LeftBracket,
+        "]" => Lexeme::RightBracket,
         "," => Lexeme::Comma,
         "let" => Lexeme::Let,
+        "rec" => Lexeme::Rec,
+        "and" => Lexeme::And,
         "in" => Lexeme::In,
+        "def" => Lexeme::Def,
+        "true" => Lexeme::Boolean(true),
+        "false" => Lexeme::Boolean(false),
+        "if" => Lexeme::If,
+        "then" => Lexeme::Then,
+        "else" => Lexeme::Else,
+        "not" => Lexeme::Not,
+        "fix" => Lexeme::Primitive(Primitive::Fix),
+        "fst" => Lexeme::Primitive(Primitive::Fst),
+        "snd" => Lexeme::Primitive(Primitive::Snd),
+        "nil" => Lexeme::Nil,
+        "cons" => Lexeme::Primitive(Primitive::Cons),
+        "head" => Lexeme::Primitive(Primitive::Head),
+        "tail" => Lexeme::Primitive(Primitive::Tail),
+        "isnil" => Lexeme::Primitive(Primitive::IsNil),
+        "map" => Lexeme::Primitive(Primitive::Map),
+        "fold" => Lexeme::Primitive(Primitive::Fold),
+        "length" => Lexeme::Primitive(Primitive::Length),
+        "concat" => Lexeme::Primitive(Primitive::Concat),
+        "charAt" => Lexeme::Primitive(Primitive::CharAt),
+        "show" => Lexeme::Primitive(Primitive::Show),
         "=" => Lexeme::Equals,
         _ => {
             if input.chars().all(char::is_numeric) {
-                Lexeme::Integer(input.parse::<i64>().unwrap())
-            } else if input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                Lexeme::Integer(input.parse::<i64>().ok()?)
+            } else if input.starts_with(char::is_alphabetic) && input.chars().all(|c| (c.is_alphanumeric() && c != 'λ') || c == '_' || c == '\'') {
                 Lexeme::Identifier(input)
             } else {
-                panic!("Invalid lexeme")
+                return None;
             }
         }
+    };
+
+    Some(lexeme)
+}
+
+impl fmt::Display for Lexeme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lexeme::Identifier(name) => write!(f, "identifier `{}`", name),
+            Lexeme::Integer(value) => write!(f, "integer `{}`", value),
+            Lexeme::Boolean(value) => write!(f, "boolean `{}`", value),
+            Lexeme::String(value) => write!(f, "string `{}`", Constant::String(value.clone())),
+            Lexeme::Character(value) => write!(f, "character `{}`", Constant::Character(*value)),
+            Lexeme::Nil => write!(f, "`nil`"),
+            Lexeme::Primitive(primitive) => write!(f, "`{}`", primitive.name()),
+            Lexeme::BinaryOperator(operator) => write!(f, "`{}`", operator),
+            Lexeme::Let => write!(f, "`let`"),
+            Lexeme::Rec => write!(f, "`rec`"),
+            Lexeme::And => write!(f, "`and`"),
+            Lexeme::In => write!(f, "`in`"),
+            Lexeme::If => write!(f, "`if`"),
+            Lexeme::Then => write!(f, "`then`"),
+            Lexeme::Else => write!(f, "`else`"),
+            Lexeme::Not => write!(f, "`not`"),
+            Lexeme::Def => write!(f, "`def`"),
+            Lexeme::Equals => write!(f, "`=`"),
+            Lexeme::Lambda => write!(f, "`\\`"),
+            Lexeme::Dot => write!(f, "`.`"),
+            Lexeme::Arrow => write!(f, "`->`"),
+            Lexeme::LeftParen => write!(f, "`(`"),
+            Lexeme::RightParen => write!(f, "`)`"),
+            Lexeme::LeftBracket => write!(f, "`[`"),
+            Lexeme::RightBracket => write!(f, "`]`"),
+            Lexeme::Comma => write!(f, "`,`"),
+        }
     }
 }
 
+///
+/// Trivia
+/// 
+/// A comment, kept verbatim, delimiters included
+/// 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    LineComment(String),
+    BlockComment(String),
+}
+
+///
+/// Token
+/// 
+/// A lexeme and where it was found, with the comments that precede it so that the 
+/// source can be reproduced, for instance by a formatter.
+/// 
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: Lexeme,
     pub line_number: usize,
+    pub column: usize,
     pub char_start: usize,
     pub char_end: usize,
-}
\ No newline at end of file
+    #[allow(dead_code)]
+    pub trivia: Vec<Trivia>,
+}
+
+impl Token {
+    pub fn span(&self) -> Span {
+        Span::new(self.char_start, self.char_end, self.line_number, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_are_kept_with_the_next_token() {
+        let mut lexer = Lexer::new("x -- first\n{- second {- nested -} -} y -- last".to_string());
+        let tokens = lexer.scan().unwrap();
+
+        assert!(tokens[0].trivia.is_empty());
+        assert_eq!(tokens[1].trivia, [
+            Trivia::LineComment("-- first".to_string()),
+            Trivia::BlockComment("{- second {- nested -} -}".to_string()),
+        ]);
+        assert_eq!(lexer.trailing_trivia(), [Trivia::LineComment("-- last".to_string())]);
+    }
+}
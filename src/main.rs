@@ -1,30 +1,30 @@
-///
-/// Alabasta: a λ-expr REPL.
-///
-/// Some supported expressions: 
-/// 
-///     (\x. (\z. z x) x) ((\y. y) 1 + 1)
-/// 
-///     (\z. (\y. (\x. x y) y z)) 1
-/// 
-///     (\x. \y. \z. x y z) 1 2 3
-/// 
-///     x y z
-/// 
-///     (\f. \x. f (f x)) (\y. y * 2) 3
-/// 
-///     let double = \x. x * 2 in double 5
-///
-///     let add = \x. \y. x + y in
-///         let sub = \x. \y. x - y in
-///         let mul = \x. \y. x * y in
-///         let square = \x. mul x x in
-///         let cube = \x. mul (mul x x) x in
-///         let x = 5 in
-///         let y = 3 in
-///         let z = add (square x) (cube y) in
-///         z
-///     
+//!
+//! Alabasta: a λ-expr REPL.
+//!
+//! Some supported expressions: 
+//! 
+//!     (\x. (\z. z x) x) ((\y. y) 1 + 1)
+//! 
+//!     (\z. (\y. (\x. x y) y z)) 1
+//! 
+//!     (\x. \y. \z. x y z) 1 2 3
+//! 
+//!     x y z
+//! 
+//!     (\f. \x. f (f x)) (\y. y * 2) 3
+//! 
+//!     let double = \x. x * 2 in double 5
+//!
+//!     let add = \x. \y. x + y in
+//!         let sub = \x. \y. x - y in
+//!         let mul = \x. \y. x * y in
+//!         let square = \x. mul x x in
+//!         let cube = \x. mul (mul x x) x in
+//!         let x = 5 in
+//!         let y = 3 in
+//!         let z = add (square x) (cube y) in
+//!         z
+//!     
 
 mod lexer;
 mod parser;
@@ -32,31 +32,82 @@ mod ast;
 mod convert;
 mod alpha;
 mod beta;
+mod debruijn;
 mod pretty;
+mod layout;
+mod environment;
+mod equivalence;
+mod encoding;
+mod diagnostic;
+mod error;
 
 use lexer::Lexer;
 use parser::Parser;
-use convert::{
-    to_normal_form,
-    from_normal_form,
+use convert::to_normal_form;
+use ast::{
+    ExpressionNode,
+    Statement,
+    VariableNode
+};
+use debruijn::to_de_bruijn;
+use diagnostic::Diagnostic;
+use encoding::{
+    encode_lists,
+    ListEncoding
 };
-use alpha::AlphaConverter;
+use environment::Environment;
+use equivalence::{
+    equivalence,
+    Equivalence
+};
+use error::AlabastaError;
 use beta::{
     BetaReducer, 
-    NormalExpressionNode
+    Fuel,
+    NormalExpressionNode,
+    ReductionStrategy
 };
 use pretty::{
+    pretty_print_nameless,
     pretty_print_normal,
+    pretty_print_redex,
     pretty_print
 };
 
+use clap::{
+    Arg,
+    Command
+};
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
+use std::{
+    fs,
+    io::IsTerminal,
+    process::ExitCode,
+    thread
+};
+
+// Reduction, conversion and printing recurse over the structure of terms, so the session
+// runs on a thread whose stack is deep enough for any term within the largest size limit
+const STACK_SIZE: usize = 1 << 30;
+
+// A term that failed to normalise may be as large as the size limit, so only this much
+// of it, in depth and in nodes, is printed as the partial result
+const PARTIAL_DEPTH: usize = 24;
+const PARTIAL_NODES: usize = 200;
 
 struct EvalOptions {
     show_parse: bool,
     show_alpha_conversion: bool,
     verbose_output: bool,
+    show_trace: bool,
+    show_internal: bool,
+    eta: bool,
+    strategy: ReductionStrategy,
+    lists: ListEncoding,
+    fuel: Fuel,
+    width: usize,
+    colour: bool,
 }
 
 impl EvalOptions {
@@ -65,9 +116,31 @@ impl EvalOptions {
             show_parse: false,
             show_alpha_conversion: false,
             verbose_output: false,
+            show_trace: false,
+            show_internal: false,
+            eta: false,
+            strategy: ReductionStrategy::default(),
+            lists: ListEncoding::default(),
+            fuel: Fuel::default(),
+            width: EvalOptions::terminal_width(),
+            colour: EvalOptions::terminal_colour(),
         }
     }
 
+    // The width of the terminal, as exported by the shell, or 80 columns
+    fn terminal_width() -> usize {
+        std::env::var("COLUMNS").ok()
+            .and_then(|columns| columns.parse::<usize>().ok())
+            .filter(|columns| *columns > 0)
+            .unwrap_or(80)
+    }
+
+    // Whether to colour output: only when it goes to a terminal, and not when NO_COLOR is
+    // set to anything (see no-color.org)
+    fn terminal_colour() -> bool {
+        std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+    }
+
     pub fn switch_parse(&mut self) {
         self.show_parse = !self.show_parse;
         println!("Show parsed expression {}.", if self.show_parse { "enabled" } else { "disabled" });
@@ -82,6 +155,106 @@ impl EvalOptions {
         self.verbose_output = !self.verbose_output;
         println!("Verbose output {}.", if self.verbose_output { "enabled" } else { "disabled" });
     }
+
+    pub fn switch_trace(&mut self) {
+        self.show_trace = !self.show_trace;
+        println!("Reduction trace {}.", if self.show_trace { "enabled" } else { "disabled" });
+    }
+
+    pub fn switch_internal_names(&mut self) {
+        self.show_internal = !self.show_internal;
+        println!("Show internal names {}.", if self.show_internal { "enabled" } else { "disabled" });
+    }
+
+    pub fn switch_eta(&mut self) {
+        self.eta = !self.eta;
+        println!("η-reduction {}.", if self.eta { "enabled" } else { "disabled" });
+    }
+
+    // A reducer configured with the strategy, fuel and rules selected
+    pub fn reducer(&self) -> BetaReducer {
+        let mut beta_reducer = BetaReducer::new(self.strategy);
+        beta_reducer.set_fuel(self.fuel);
+        beta_reducer.set_eta(self.eta);
+        beta_reducer.set_lists(self.lists);
+        beta_reducer
+    }
+
+    pub fn set_limit(&mut self, argument: Option<&str>) {
+        let mut arguments = argument.unwrap_or_default().split_whitespace();
+
+        match (arguments.next(), arguments.next()) {
+            (None, _) => { },
+            (Some("size"), Some(size)) => {
+                match size.parse::<usize>() {
+                    Ok(size) if size > Fuel::MAX_SIZE => {
+                        println!("Error: the size limit can be at most {} nodes.", Fuel::MAX_SIZE);
+                    },
+                    Ok(size) => self.fuel.size = size,
+                    Err(_) => println!("Error: invalid size limit '{}'.", size),
+                }
+            },
+            (Some(steps), None) => {
+                match steps.parse::<usize>() {
+                    Ok(steps) => self.fuel.steps = steps,
+                    Err(_) => println!("Error: invalid step limit '{}'.", steps),
+                }
+            },
+            _ => {
+                println!("Usage: :limit <steps> | :limit size <nodes>");
+            }
+        }
+
+        println!("Reduction limited to {} steps and {} nodes.", self.fuel.steps, self.fuel.size);
+    }
+
+    pub fn set_width(&mut self, argument: Option<&str>) {
+        match argument.map(str::parse::<usize>) {
+            None => { },
+            Some(Ok(width)) if width > 0 => self.width = width,
+            Some(_) => println!("Error: invalid width '{}'.", argument.unwrap_or_default()),
+        }
+
+        println!("Output width is {} columns.", self.width);
+    }
+
+    pub fn set_strategy(&mut self, name: Option<&str>) {
+        match name.map(str::parse::<ReductionStrategy>) {
+            Some(Ok(strategy)) => {
+                self.strategy = strategy;
+                println!("Reduction strategy set to {}.", self.strategy);
+                return;
+            },
+            Some(Err(err)) => {
+                println!("Error: {}", err);
+            },
+            None => {
+                println!("Reduction strategy is {}.", self.strategy);
+            }
+        }
+
+        let names: Vec<&str> = ReductionStrategy::ALL.iter().map(|strategy| strategy.name()).collect();
+        println!("Available strategies: {}.", names.join(", "));
+    }
+
+    pub fn set_lists(&mut self, name: Option<&str>) {
+        match name.map(str::parse::<ListEncoding>) {
+            Some(Ok(lists)) => {
+                self.lists = lists;
+                println!("Reducing {}.", self.lists);
+                return;
+            },
+            Some(Err(err)) => {
+                println!("Error: {}", err);
+            },
+            None => {
+                println!("Reducing {}.", self.lists);
+            }
+        }
+
+        let names: Vec<&str> = ListEncoding::ALL.iter().map(|lists| lists.name()).collect();
+        println!("Available list encodings: {}.", names.join(", "));
+    }
 }
 
 fn show_help() {
@@ -91,52 +264,122 @@ fn show_help() {
     println!("    :verbose, :v - enable verbose output");
     println!("    :show-parse, :sp - show parsed expression");
     println!("    :show-alpha, :sa - show alpha conversion");
+    println!("    :trace, :t - show every contraction, with the redex highlighted");
+    println!("    :show-internal - show the internal names given to bound variables");
+    println!("    :eta - enable η-reduction (normal and applicative order)");
+    println!("    :eta-expand <expr> - η-expand an expression");
+    println!("    :limit <n>, :limit size <n> - limit the number of contractions or the size of terms");
+    println!("    :width <n> - break output into lines of at most n columns");
+    println!("    def <name> = <expr>, :let <name> = <expr> - define a name for later expressions");
+    println!("    :env - list definitions");
+    println!("    :undef <name> - remove a definition");
+    println!("    :clear - remove all definitions");
+    println!("    :load <file> - load the definitions in a source file");
+    println!("    :reload - load the last file loaded again");
+    println!("    :step <expr> - step through a reduction ([enter] next, b back, c continue, r [n] list/pick redex)");
+    println!("    :equiv <expr> ; <expr> - check whether two expressions are α- or βη-equivalent");
+    println!("    :debruijn <expr> - print an expression and its normal form in nameless form, with bound variables as De Bruijn indices");
+    println!("    :strategy, :st <name> - set reduction strategy (normal, applicative, name, value, need)");
+    println!("    :lists <name> - reduce lists natively or desugared into λ-terms (native, church, scott)");
     println!("    :reference, :r - print reference");
     println!("    :help, :h - print this help message");
     println!("    :exit, :quit, :q, :x - exit the REPL");
 }
 
+fn show_environment(options: &EvalOptions, environment: &Environment) {
+    if environment.definitions().is_empty() {
+        println!("No definitions.");
+    }
+
+    for (name, value) in environment.definitions() {
+        pretty_print_normal(&format!("{} = ", name), &displayed(value, options, environment), options.width);
+        println!();
+    }
+}
+
 fn show_reference() {
-    println!(r"
+    println!(r#"
     +---------------------------------------------------+
     |                 Alabasta Reference                |
     +---------------------------------------------------+
     1. Lambda Abstractions:
-       - Use the pattern: \<variable>.<expression>
+       - Use the pattern: \<variable>.<expression>  (or λ<variable>.<expression>, \<variable> -> <expression>)
        - Example: \x.x + 1
+       - Several variables abstract in turn: \x y. x + y is \x. \y. x + y
     
     2. Arithmetic Operations:
-       - Supported operators: +, -, *, /, %
-       - Example: (3 + 5) * 2
+       - Supported operators: +, -, *, /, % and unary minus
+       - *, / and % bind tighter than + and -, which bind tighter than comparisons; application binds tightest
+       - Example: (3 + 5) * 2, 1 + 2 * 3, -x * 2
     
-    3. Let Expressions:
+    3. Booleans and Conditionals:
+       - Constants true and false; comparisons ==, !=, <, <=, >, >=; logical &&, || and not
+       - Use the pattern: if <condition> then <expression> else <expression>
+       - Only the branch chosen is evaluated, and && and || stop once the result is known
+       - Example: if x < 0 then -x else x, not (x == 0) && 10 / x > 1
+    
+    4. Let Expressions:
        - Use the pattern: let <variable> = <expression> in <scope_expression>
        - Example: let double = \x.x * 2 in double 5
+       - Functions take parameters: let add x y = x + y in add 2 3
+       - Recursive functions use let rec, joined by and when they call each other:
+         let rec fact n = if n == 0 then 1 else n * fact (n - 1) in fact 5
+       - fix takes the fixpoint of a function: fix (\f n. if n == 0 then 1 else n * f (n - 1)) 5
+       - Recursive bindings unfold only when their value is needed
+       - A tuple pattern binds the components of a tuple: let (x, y) = (1, 2) in x + y
     
-    4. Lambda Application:
+    5. Lambda Application:
        - Use the pattern: <lambda_expression> <argument>
        - Example: (\x.x + 1) 5
     
-    5. Special Notes:
-       - Variables must start with a letter and can include alphanumeric characters and underscores.
+    6. Tuples:
+       - Use the pattern: (<expression>, <expression>, ...)
+       - Components are numbered from 0: (1, 2).1 is 2; fst t and snd t are t.0 and t.1
+       - Projection binds tighter than application: f t.0 is f (t.0)
+    
+    7. Lists:
+       - Use the pattern: [<expression>, <expression>, ...], or nil and <head> :: <tail>
+       - :: associates to the right and binds looser than + and -: 1 :: 2 :: nil is [1, 2]
+       - cons, head, tail, isnil, map, fold and length: fold (\x acc. x + acc) 0 [1, 2, 3] is 6
+       - :lists church or :lists scott desugars lists into λ-terms; :lists native restores them
+    
+    8. Strings and Characters:
+       - Use the pattern: "<characters>" for a string, '<character>' for a character
+       - Escapes: \n \t \r \0 \\ \" \' and \u{{<hex>}}, as in "λ is \u{{3bb}}"
+       - concat, length, charAt (from 0) and show (of an integer or a boolean):
+         concat "answer: " (show 42), charAt "abc" 1 is 'b'
+       - ==, !=, <, <=, > and >= compare characters by code point and strings lexicographically
+    
+    9. Definitions:
+       - Use the pattern: def <variable> = <expression>  (or <variable> = <expression>)
+       - Example: def square = \x.x * x
+       - Definitions are available to every later expression; list them with :env
+    
+    10. Reduction Strategies:
+       - Use the pattern: :strategy <name>
+       - normal (default), applicative, name (call-by-name), value (call-by-value), need (call-by-need)
+       - Example: (\x. 1) ((\y. y y) (\y. y y)) terminates under normal, name and need only
+    
+    11. Special Notes:
+       - Variables must start with a letter, in any script, and can include alphanumeric characters, underscores and primes (x').
        - Parentheses can be used to specify evaluation order.
        - Expressions should be separated by whitespace.
     
-    6. Examples:
-       - Example 1: (\x.\y.x + y) 5 10    (Applies lambda function to arguments)
-       - Example 2: let square = \x.x * x in square 5    (Using let expressions)
-       - Example 3: let add = \x. \y. x + y in     (Using multiline expressions)
-                    let sub = \x. \y. x - y in
-                    let mul = \x. \y. x * y in
-                    let square = \x. mul x x in
-                    let cube = \x. mul (mul x x) x in
-                    let x = 5 in
-                    let y = 3 in
-                    let z = add (square x) (cube y) in
-                    z
+    12. Examples:
+        - Example 1: (\x.\y.x + y) 5 10    (Applies lambda function to arguments)
+        - Example 2: let square = \x.x * x in square 5    (Using let expressions)
+        - Example 3: let add = \x. \y. x + y in     (Using multiline expressions)
+                     let sub = \x. \y. x - y in
+                     let mul = \x. \y. x * y in
+                     let square = \x. mul x x in
+                     let cube = \x. mul (mul x x) x in
+                     let x = 5 in
+                     let y = 3 in
+                     let z = add (square x) (cube y) in
+                     z
     
     Happy experimenting with Alabasta! Type ':quit' to exit the REPL.
-    ");
+    "#);
 }
 
 fn show_welcome() {
@@ -157,17 +400,159 @@ fn show_welcome() {
 ");
 }
 
-fn main() -> rustyline::Result<()>{
+fn cli() -> Command {
+    Command::new("alabasta")
+        .about("A λ-calculus REPL")
+        .arg(Arg::new("strategy")
+            .long("strategy")
+            .short('s')
+            .global(true)
+            .value_name("NAME")
+            .help("Reduction strategy: normal, applicative, name, value or need"))
+        .arg(Arg::new("eta")
+            .long("eta")
+            .global(true)
+            .action(clap::ArgAction::SetTrue)
+            .help("Enable η-reduction (normal and applicative order)"))
+        .arg(Arg::new("limit")
+            .long("limit")
+            .global(true)
+            .value_name("STEPS")
+            .value_parser(clap::value_parser!(usize))
+            .help("Maximum number of contractions per expression"))
+        .arg(Arg::new("width")
+            .long("width")
+            .global(true)
+            .value_name("COLUMNS")
+            .value_parser(clap::value_parser!(usize))
+            .help("Width to break output at (defaults to $COLUMNS, or 80)"))
+        .subcommand(Command::new("run")
+            .about("Run a source file: load its definitions and evaluate its main expression")
+            .arg(Arg::new("file").required(true)))
+        .subcommand(Command::new("repl")
+            .about("Start the interactive REPL (default)")
+            .arg(Arg::new("load")
+                .long("load")
+                .short('l')
+                .value_name("FILE")
+                .help("Load definitions from a source file")))
+        .subcommand(Command::new("equiv")
+            .about("Check whether two expressions are α- or βη-equivalent, exiting with 1 if they are not and 2 on error")
+            .arg(Arg::new("first").required(true).value_name("EXPR").allow_hyphen_values(true))
+            .arg(Arg::new("second").required(true).value_name("EXPR").allow_hyphen_values(true))
+            .arg(Arg::new("load")
+                .long("load")
+                .short('l')
+                .value_name("FILE")
+                .help("Load definitions from a source file")))
+        .subcommand(Command::new("eval")
+            .about("Evaluate a single expression")
+            .arg(Arg::new("expression")
+                .short('e')
+                .long("expression")
+                .required(true)
+                .value_name("EXPR")
+                .allow_hyphen_values(true)))
+}
+
+fn main() -> ExitCode {
+    let session = thread::Builder::new()
+        .name("session".to_string())
+        .stack_size(STACK_SIZE)
+        .spawn(session)
+        .expect("the session thread can be spawned");
+
+    session.join().unwrap_or(ExitCode::FAILURE)
+}
+
+fn session() -> ExitCode {
+    let matches = cli().get_matches();
+    let mut eval_options = EvalOptions::new();
+
+    if let Some(strategy) = matches.get_one::<String>("strategy") {
+        match strategy.parse::<ReductionStrategy>() {
+            Ok(strategy) => eval_options.strategy = strategy,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    eval_options.eta = matches.get_flag("eta");
+
+    if let Some(steps) = matches.get_one::<usize>("limit") {
+        eval_options.fuel.steps = *steps;
+    }
+
+    if let Some(width) = matches.get_one::<usize>("width") {
+        eval_options.width = (*width).max(1);
+    }
+
+    let result = match matches.subcommand() {
+        Some(("run", arguments)) => {
+            let file = arguments.get_one::<String>("file").unwrap();
+            run(file, &eval_options)
+        },
+        Some(("eval", arguments)) => {
+            let expression = arguments.get_one::<String>("expression").unwrap();
+            eval(expression, &eval_options, &mut Environment::new())
+        },
+        Some(("equiv", arguments)) => {
+            let first = arguments.get_one::<String>("first").unwrap();
+            let second = arguments.get_one::<String>("second").unwrap();
+            let file = arguments.get_one::<String>("load").map(String::as_str);
+
+            let mut environment = Environment::new();
+            let result = file
+                .map_or(Ok(()), |file| load(file, &eval_options, &mut environment).map(|_| ()))
+                .and_then(|_| check_equivalence(first, second, &eval_options, &mut environment));
+
+            // As with cmp and diff, a negative answer is told apart from a failure
+            match result {
+                Ok(true) => Ok(()),
+                Ok(false) => return ExitCode::FAILURE,
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    return ExitCode::from(2);
+                }
+            }
+        },
+        Some(("repl", arguments)) => {
+            let file = arguments.get_one::<String>("load").map(String::as_str);
+            repl(file, eval_options)
+        },
+        _ => {
+            repl(None, eval_options)
+        }
+    };
+
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn repl(file: Option<&str>, mut eval_options: EvalOptions) -> Result<(), AlabastaError> {
 
     show_welcome();
 
     let mut prompt;
     let mut multiline = false;
     let mut lambda_expression = String::new();
-    let mut eval_options = EvalOptions::new();
+    let mut environment = Environment::new();
+    let mut loaded = file.map(str::to_string);
+
+    if let Some(file) = file {
+        load_into_session(file, &eval_options, &mut environment);
+    }
 
     // Initialise rustyline
-    let mut rl = DefaultEditor::new()?;
+    let mut rl = DefaultEditor::new()
+        .map_err(|err| AlabastaError::Io(format!("unable to initialise the terminal: {}", err)))?;
     #[cfg(feature = "with-file-history")]
     if rl.load_history("history.txt").is_err() {
         println!("No previous history.");
@@ -176,7 +561,7 @@ fn main() -> rustyline::Result<()>{
     // REPL loop
     loop {
         // Set prompt
-        if multiline == false || lambda_expression.len() == 0 {
+        if !multiline || lambda_expression.is_empty() {
             prompt = "λ-expr >> ".to_string();
         } else {
             prompt = "+ > ".to_string();
@@ -224,23 +609,150 @@ fn main() -> rustyline::Result<()>{
                         eval_options.switch_verbose_output();
                         continue
                     },
+                    ref s if s == ":trace" || s == ":t" => {
+                        eval_options.switch_trace();
+                        continue
+                    },
+                    ref s if s == ":show-internal" => {
+                        eval_options.switch_internal_names();
+                        continue
+                    },
+                    ref s if s == ":eta" => {
+                        eval_options.switch_eta();
+                        continue
+                    },
+                    ref s if command_matches(s, &[":eta-expand"]) => {
+                        match command_argument(s) {
+                            Some(expression) => {
+                                let _ = rl.add_history_entry(s.as_str());
+
+                                if let Err(err) = eta_expand(expression, &eval_options, &mut environment) {
+                                    println!("Error: {}", err);
+                                }
+                            },
+                            None => println!("Usage: :eta-expand <expression>"),
+                        }
+                        continue
+                    },
+                    ref s if command_matches(s, &[":strategy", ":st"]) => {
+                        eval_options.set_strategy(command_argument(s));
+                        continue
+                    },
+                    ref s if command_matches(s, &[":lists"]) => {
+                        eval_options.set_lists(command_argument(s));
+                        continue
+                    },
+                    ref s if command_matches(s, &[":width"]) => {
+                        eval_options.set_width(command_argument(s));
+                        continue
+                    },
+                    ref s if command_matches(s, &[":limit"]) => {
+                        eval_options.set_limit(command_argument(s));
+                        continue
+                    },
+                    ref s if command_matches(s, &[":let", ":def"]) => {
+                        match command_argument(s) {
+                            Some(definition) => {
+                                let _ = rl.add_history_entry(s.as_str());
+
+                                if let Err(err) = eval(&format!("def {}", definition), &eval_options, &mut environment) {
+                                    println!("Error: {}", err);
+                                }
+                            },
+                            None => println!("Usage: :let <name> = <expression>"),
+                        }
+                        continue
+                    },
+                    ref s if s == ":env" => {
+                        show_environment(&eval_options, &environment);
+                        continue
+                    },
+                    ref s if command_matches(s, &[":undef"]) => {
+                        match command_argument(s) {
+                            Some(name) if environment.undefine(name) => println!("{} removed.", name),
+                            Some(name) => println!("Error: {}", AlabastaError::Unbound(name.to_string())),
+                            None => println!("Usage: :undef <name>"),
+                        }
+                        continue
+                    },
+                    ref s if s == ":clear" => {
+                        environment.clear();
+                        println!("Definitions cleared.");
+                        continue
+                    },
+                    ref s if command_matches(s, &[":load"]) => {
+                        match command_argument(s) {
+                            Some(file) => {
+                                load_into_session(file, &eval_options, &mut environment);
+                                loaded = Some(file.to_string());
+                            },
+                            None => println!("Usage: :load <file>"),
+                        }
+                        continue
+                    },
+                    ref s if s == ":reload" => {
+                        match &loaded {
+                            Some(file) => load_into_session(file, &eval_options, &mut environment),
+                            None => println!("Error: no file has been loaded."),
+                        }
+                        continue
+                    },
+                    ref s if command_matches(s, &[":equiv"]) => {
+                        match command_argument(s).and_then(|argument| argument.split_once(';')) {
+                            Some((first, second)) => {
+                                let _ = rl.add_history_entry(s.as_str());
+
+                                if let Err(err) = check_equivalence(first, second, &eval_options, &mut environment) {
+                                    println!("Error: {}", err);
+                                }
+                            },
+                            None => println!("Usage: :equiv <expression> ; <expression>"),
+                        }
+                        continue
+                    },
+                    ref s if command_matches(s, &[":debruijn"]) => {
+                        match command_argument(s) {
+                            Some(expression) => {
+                                let _ = rl.add_history_entry(s.as_str());
+
+                                if let Err(err) = reduce_nameless(expression, &eval_options, &mut environment) {
+                                    println!("Error: {}", err);
+                                }
+                            },
+                            None => println!("Usage: :debruijn <expression>"),
+                        }
+                        continue
+                    },
+                    ref s if command_matches(s, &[":step"]) => {
+                        match command_argument(s) {
+                            Some(expression) => {
+                                let _ = rl.add_history_entry(s.as_str());
+
+                                if let Err(err) = step_through(expression, &eval_options, &mut environment, &mut rl) {
+                                    println!("Error: {}", err);
+                                }
+                            },
+                            None => println!("Usage: :step <expression>"),
+                        }
+                        continue
+                    },
                     _ => { }
                 }
 
                 // if multiline is disabled, evaluate the input
-                if multiline == false {
+                if !multiline {
                     // add input to command history
                     let _ = rl.add_history_entry(line.as_str());
 
                     // evaluate lambda expression
-                    if let Err(err) = eval(&line.to_string(), &eval_options) {
+                    if let Err(err) = eval(&line, &eval_options, &mut environment) {
                         println!("Error: {}", err);
                     }
                 } else {
                     // if line is empty, evaluate the lambda expression
-                    if line.len() == 0 {
+                    if line.is_empty() {
                         let _ = rl.add_history_entry(lambda_expression.as_str());
-                        if let Err(err) = eval(&&lambda_expression.to_string(), &eval_options) {
+                        if let Err(err) = eval(&lambda_expression, &eval_options, &mut environment) {
                             println!("Error: {}", err);
                         }
 
@@ -248,7 +760,9 @@ fn main() -> rustyline::Result<()>{
                         lambda_expression = String::new();
                     } else {
                         // concatenate the current line to the lambda expression
-                        lambda_expression.push_str(" ");
+                        if !lambda_expression.is_empty() {
+                            lambda_expression.push('\n');
+                        }
                         lambda_expression.push_str(line.as_str());
                     }
                 }
@@ -268,49 +782,437 @@ fn main() -> rustyline::Result<()>{
         }
     }
     #[cfg(feature = "with-file-history")]
-    rl.save_history("history.txt");
+    let _ = rl.save_history("history.txt");
 
     Ok(())
 }
 
 ///
-///  Evaluate a lambda expression
+/// Check whether a line is the given command, with or without arguments
 /// 
-fn eval(lambda_expression: &String, options: &EvalOptions) -> Result<NormalExpressionNode, String> {
+fn command_matches(line: &str, names: &[&str]) -> bool {
+    line.split_whitespace()
+        .next()
+        .is_some_and(|command| names.contains(&command))
+}
+
+///
+/// Everything following the command name on a line
+/// 
+fn command_argument(line: &str) -> Option<&str> {
+    line.trim()
+        .split_once(char::is_whitespace)
+        .map(|(_, argument)| argument.trim())
+        .filter(|argument| !argument.is_empty())
+}
+
+///
+///  Parse a statement: a definition or an expression
+/// 
+fn parse(lambda_expression: &str) -> Result<Statement, AlabastaError> {
     // Generate token list from input string
     let token_list = Lexer::new(lambda_expression.to_string())
-        .scan()?;
+        .scan()
+        .map_err(|err| err.located(lambda_expression, "<input>"))?;
 
     // Parse token list into abstract syntax tree
-    let ast = Parser::new(token_list)
-        .parse()?;
+    Parser::new(token_list)
+        .parse_statement()
+        .map_err(|err| err.located(lambda_expression, "<input>"))
+}
+
+///
+///  Parse an expression, for commands that do not accept definitions
+/// 
+fn expression(lambda_expression: &str) -> Result<ExpressionNode, AlabastaError> {
+    match parse(lambda_expression)? {
+        Statement::Expression(ast) => Ok(ast),
+        Statement::Definition(variable, _) => {
+            let diagnostic = Diagnostic::expected("an expression", "a definition", variable.span);
+            Err(AlabastaError::Parse(diagnostic.located(lambda_expression, "<input>")))
+        }
+    }
+}
+
+///
+///  Alpha convert an expression and resolve session definitions, then encode its lists as
+///  asked, ready for reduction
+/// 
+fn prepare(ast: &ExpressionNode, options: &EvalOptions, environment: &mut Environment) -> NormalExpressionNode {
+    let resolved = resolve(ast, options, environment);
+
+    encode_lists(&resolved, options.lists, environment)
+}
 
+///
+///  Alpha convert an expression and resolve session definitions. Definitions keep their
+///  lists native, so that they can be encoded as each expression using them asks.
+/// 
+fn resolve(ast: &ExpressionNode, options: &EvalOptions, environment: &mut Environment) -> NormalExpressionNode {
+    let converted = convert(ast, options, environment);
+
+    // Substitute session definitions for the free variables naming them
+    environment.resolve(&converted)
+}
+
+///
+///  Alpha convert an expression, without resolving session definitions
+/// 
+fn convert(ast: &ExpressionNode, options: &EvalOptions, environment: &mut Environment) -> NormalExpressionNode {
     // Pretty print the parsed input
     if options.show_parse {
         if options.verbose_output { println!("Parsed λ-expr :"); }
-        print!("=> "); pretty_print(&ast);
+        pretty_print("=> ", ast, options.width);
         println!();
     }
     
     // Perform alpha conversion on the abstract syntax tree
-    let _ = AlphaConverter::new()
-        .convert(&ast);
+    environment.alpha_convert(ast);
 
     // Print the normal form
     if options.show_alpha_conversion {
         if options.verbose_output { println!("α-conversion :"); }
-        print!("=> "); pretty_print(&ast);
+        pretty_print("=> ", ast, options.width);
         println!();
     }
 
+    to_normal_form(ast)
+}
+
+///
+///  Evaluate a statement, binding definitions in the session environment
+/// 
+fn eval(lambda_expression: &str, options: &EvalOptions, environment: &mut Environment) -> Result<(), AlabastaError> {
+    match parse(lambda_expression)? {
+        Statement::Definition(variable, expression) => {
+            let name = define(&variable, &expression, options, environment);
+            println!("{} defined.", name);
+        },
+        Statement::Expression(expression) => {
+            reduce(&expression, options, environment)?;
+        }
+    }
+
+    Ok(())
+}
+
+///
+///  Bind a definition in the session environment, returning the name defined
+/// 
+fn define(variable: &VariableNode, expression: &ExpressionNode, options: &EvalOptions, environment: &mut Environment) -> String {
+    let name = variable.name.borrow().clone();
+    // The environment resolves the value as it binds it
+    let value = convert(expression, options, environment);
+
+    environment.define(&name, value);
+
+    name
+}
+
+///
+///  Load a source file, binding its definitions in the session environment. Returns
+///  the number of definitions made, and the expressions found in the file.
+/// 
+fn load(file: &str, options: &EvalOptions, environment: &mut Environment) -> Result<(usize, Vec<ExpressionNode>), AlabastaError> {
+    let source = fs::read_to_string(file)
+        .map_err(|err| AlabastaError::Io(format!("unable to read {}: {}", file, err)))?;
+
+    let token_list = Lexer::new(source.clone())
+        .scan()
+        .map_err(|err| err.located(&source, file))?;
+
+    let statements = Parser::new(token_list)
+        .parse_program()
+        .map_err(|err| err.located(&source, file))?;
+
+    let mut definitions = 0;
+    let mut expressions = Vec::new();
+
+    for statement in statements {
+        match statement {
+            Statement::Definition(variable, expression) => {
+                define(&variable, &expression, options, environment);
+                definitions += 1;
+            },
+            Statement::Expression(expression) => {
+                expressions.push(expression);
+            }
+        }
+    }
+
+    Ok((definitions, expressions))
+}
+
+///
+///  Load the definitions in a source file into the REPL session
+/// 
+fn load_into_session(file: &str, options: &EvalOptions, environment: &mut Environment) {
+    match load(file, options, environment) {
+        Ok((definitions, _)) => println!("Loaded {} definitions from {}.", definitions, file),
+        Err(err) => println!("Error: {}", err),
+    }
+}
+
+///
+///  Run a source file: evaluate the expressions it contains, or its main definition
+/// 
+fn run(file: &str, options: &EvalOptions) -> Result<(), AlabastaError> {
+    let mut environment = Environment::new();
+    let (_, mut expressions) = load(file, options, &mut environment)?;
+
+    // Without expressions to evaluate, the file must define main
+    if expressions.is_empty() {
+        if environment.lookup("main").is_none() {
+            return Err(AlabastaError::Unbound("main".to_string()));
+        }
+
+        expressions.push(ExpressionNode::Variable(VariableNode::new("main")));
+    }
+
+    for expression in expressions {
+        reduce(&expression, options, &mut environment)?;
+    }
+
+    Ok(())
+}
+
+///
+///  An expression as it is shown: with the names the user wrote, unless internal names were asked for
+/// 
+fn displayed(node: &NormalExpressionNode, options: &EvalOptions, environment: &Environment) -> NormalExpressionNode {
+    if options.show_internal {
+        node.clone()
+    } else {
+        environment.restore_names(node)
+    }
+}
+
+///
+///  Reduce a lambda expression
+/// 
+fn reduce(ast: &ExpressionNode, options: &EvalOptions, environment: &mut Environment) -> Result<NormalExpressionNode, AlabastaError> {
+    let normal_form = prepare(ast, options, environment);
+
     // Perform beta reduction on the abstract syntax tree
-    let mut beta_reducer = BetaReducer::new();
-    let normal_form = to_normal_form(&ast);
+    let mut beta_reducer = options.reducer();
+    beta_reducer.set_tracing(options.show_trace);
+
     let result = beta_reducer.reduce(&normal_form);
 
+    // Print every contraction, highlighting the redex contracted
+    if options.show_trace {
+        if options.verbose_output { println!("Reduction trace ({}) :", beta_reducer.strategy()); }
+        for (index, contraction) in beta_reducer.trace().iter().enumerate() {
+            let prefix = format!("{:>4} {:<3} ", index + 1, contraction.rule);
+            pretty_print_redex(&prefix, &displayed(&contraction.before, options, environment), &contraction.path, options.width, options.colour);
+            println!();
+
+            if options.verbose_output {
+                pretty_print_normal("         redex: ", &displayed(&contraction.redex, options, environment), options.width); println!();
+                pretty_print_normal("         after: ", &displayed(&contraction.after, options, environment), options.width); println!();
+            }
+        }
+    }
+
+    // Print the partial result if the expression did not normalise
+    let result = match result {
+        Ok(result) => result,
+        Err(AlabastaError::ResourceLimit(divergence)) => {
+            if options.verbose_output { println!("Partial result :"); }
+            let term = divergence.term();
+            let partial = term.elided(PARTIAL_DEPTH, PARTIAL_NODES);
+
+            pretty_print_normal("=> ", &displayed(&partial, options, environment), options.width);
+            println!();
+
+            if partial != *term {
+                println!("   (partial result of {} nodes, elided)", term.size());
+            }
+
+            return Err(AlabastaError::ResourceLimit(divergence));
+        },
+        Err(err) => {
+            return Err(err);
+        }
+    };
+
     // Print the normal form
-    if options.verbose_output { println!("Normal Form (after β-reductions) :"); }
-    print!("=> "); pretty_print_normal(&result);
+    if options.verbose_output { println!("Normal Form (after β-reductions, {}) :", beta_reducer.strategy()); }
+    pretty_print_normal("=> ", &displayed(&result, options, environment), options.width);
+    println!();
+
+    Ok(result)
+}
+
+///
+///  Check whether two lambda expressions are equivalent, printing the outcome
+/// 
+fn check_equivalence(first: &str, second: &str, options: &EvalOptions, environment: &mut Environment) -> Result<bool, AlabastaError> {
+    let first = prepare(&expression(first)?, options, environment);
+    let second = prepare(&expression(second)?, options, environment);
+
+    let outcome = equivalence(&first, &second, options.fuel, options.lists)?;
+
+    match &outcome {
+        Equivalence::Alpha => {
+            println!("α-equivalent.");
+        },
+        Equivalence::BetaEta(normal_form) => {
+            println!("βη-equivalent, with normal form:");
+            pretty_print_normal("=> ", &displayed(normal_form, options, environment), options.width);
+            println!();
+        },
+        Equivalence::Distinct(first, second) => {
+            println!("Not equivalent; the normal forms differ:");
+            pretty_print_normal("=> ", &displayed(first, options, environment), options.width);
+            println!();
+            pretty_print_normal("=> ", &displayed(second, options, environment), options.width);
+            println!();
+        }
+    }
+
+    Ok(outcome.holds())
+}
+
+///
+///  η-expand a lambda expression: e becomes λx. e x, for an x that is named apart from
+///  every name shown in e, so that it neither captures nor shadows one
+/// 
+fn eta_expand(lambda_expression: &str, options: &EvalOptions, environment: &mut Environment) -> Result<(), AlabastaError> {
+    let normal_form = displayed(&prepare(&expression(lambda_expression)?, options, environment), options, environment);
+
+    let names = normal_form.names();
+    let mut parameter = String::from("x");
+    while names.contains(&parameter) {
+        parameter.push('\'');
+    }
+
+    pretty_print_normal("=> ", &normal_form.eta_expand(&parameter), options.width);
+    println!();
+
+    Ok(())
+}
+
+///
+///  Print a lambda expression and its normal form in nameless form (the nameless form is
+///  reduced by a round trip through the named one)
+/// 
+fn reduce_nameless(lambda_expression: &str, options: &EvalOptions, environment: &mut Environment) -> Result<(), AlabastaError> {
+    let ast = expression(lambda_expression)?;
+    let nameless = to_de_bruijn(&prepare(&ast, options, environment));
+
+    pretty_print_nameless("   ", &nameless, options.width);
+    println!();
+
+    let result = options.reducer().reduce_nameless(&nameless)?;
+
+    pretty_print_nameless("=> ", &result, options.width);
+    println!();
+
+    Ok(())
+}
+
+///
+///  Interactively step through the reduction of a lambda expression
+/// 
+///     [enter]  contract the next redex chosen by the reduction strategy
+///     b        step back
+///     c        continue to the end of the reduction
+///     r        list every redex in the current expression
+///     r <n>    contract redex n from the list
+///     q        stop stepping
+/// 
+fn step_through(lambda_expression: &str, options: &EvalOptions, environment: &mut Environment, rl: &mut DefaultEditor) -> Result<NormalExpressionNode, AlabastaError> {
+    let ast = expression(lambda_expression)?;
+
+    let mut beta_reducer = options.reducer();
+
+    let mut history = vec![prepare(&ast, options, environment)];
+
+    println!("Stepping ({}): [enter] next, b back, c continue, r [n] list/pick redex, q quit.", beta_reducer.strategy());
+
+    loop {
+        let current = history.last().unwrap().clone();
+        let next = beta_reducer.next_contraction(&current);
+
+        let prefix = format!("{:>4} ", history.len() - 1);
+        match &next {
+            Ok(Some(contraction)) => pretty_print_redex(&prefix, &displayed(&current, options, environment), &contraction.path, options.width, options.colour),
+            _ => pretty_print_normal(&prefix, &displayed(&current, options, environment), options.width),
+        }
+        println!();
+
+        match &next {
+            Ok(Some(_)) => { },
+            Ok(None) => println!("No further redexes under {}.", beta_reducer.strategy()),
+            Err(err) => println!("Error: {}", err),
+        }
+
+        let command = match rl.readline("step >> ") {
+            Ok(command) => command,
+            Err(_) => break,
+        };
+
+        match command.trim() {
+            "" => {
+                match beta_reducer.step(&current) {
+                    Ok(Some(next)) => history.push(next),
+                    Ok(None) => break,
+                    Err(_) => println!("The expression is stuck; b to step back, q to quit."),
+                }
+            },
+            "b" => {
+                if history.len() > 1 {
+                    history.pop();
+                } else {
+                    println!("Already at the first step.");
+                }
+            },
+            "c" => {
+                match beta_reducer.reduce(&current) {
+                    Ok(result) => history.push(result),
+                    Err(err) => {
+                        println!("Error: {}", err);
+
+                        // Show how far the reduction got before the fuel ran out
+                        if let AlabastaError::ResourceLimit(divergence) = err {
+                            history.push(divergence.term().clone());
+                        }
+                    }
+                }
+                break
+            },
+            "q" => {
+                break
+            },
+            "r" => {
+                for (index, path) in beta_reducer.redexes(&current).iter().enumerate() {
+                    pretty_print_redex(&format!("   [{}] ", index), &displayed(&current, options, environment), path, options.width, options.colour);
+                    println!();
+                }
+            },
+            s if command_matches(s, &["r"]) => {
+                let redexes = beta_reducer.redexes(&current);
+                let contraction = command_argument(s)
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .and_then(|index| redexes.get(index))
+                    .map(|path| beta_reducer.contract_at(&current, path));
+
+                match contraction {
+                    Some(Ok(Some(contraction))) => history.push(contraction.after),
+                    Some(Err(err)) => println!("Error: {}", err),
+                    _ => println!("No such redex; type r to list them."),
+                }
+            },
+            _ => {
+                println!("Unknown command: [enter] next, b back, c continue, r [n] list/pick redex, q quit.");
+            }
+        }
+    }
+
+    let result = history.last().unwrap().clone();
+
+    pretty_print_normal("=> ", &displayed(&result, options, environment), options.width);
     println!();
 
     Ok(result)
@@ -1,30 +1,99 @@
-///
-/// Parsing
-/// 
-/// The parser takes a list of tokens and converts them into an abstract syntax tree. Lambda expressions
-/// are left-associative with respect to applications (function calls). This means that the expression
-/// "x y z" is parsed as "(x y) z".
-/// 
-/// The language is described by the following EBNF:
-/// 
-/// Expression  :=  Variable
-///             |   Constant
-///             |   '\' Variable '.' Expression
-///             |   '(' Expression ')'
-///             |   Expression Expression
-///             |   Expression BinaryOperator Expression
-///             |   'let' Variable '=' Expression 'in' Expression
-/// 
-/// Variable    :=  Identifier
-/// 
-/// Constant    :=  Integer
-/// 
-/// BinaryOperator := '+' | '-' | '*' | '/' | '%'
-/// 
-/// Identifier  :=  [a-zA-Z][a-zA-Z_0-9]*
-/// 
-/// Integer     :=  [0-9]+
-///  
+//!
+//! Parsing
+//! 
+//! The parser takes a list of tokens and converts them into an abstract syntax tree. Lambda expressions
+//! are left-associative with respect to applications (function calls). This means that the expression
+//! "x y z" is parsed as "(x y) z".
+//! 
+//! The language is described by the following EBNF:
+//! 
+//! Program     :=  Statement*, each statement ending before the next line to start in the
+//!                 leftmost column
+//! 
+//! Statement   :=  ['def'] Variable '=' Expression
+//!             |   Expression
+//! 
+//! Expression  :=  Unary { BinaryOperator Unary }
+//! 
+//! Unary       :=  '-' Unary
+//!             |   'not' Unary
+//!             |   Application
+//! 
+//! Application :=  Projection { Projection }
+//! 
+//! Projection  :=  Atom { '.' Integer }
+//! 
+//! Atom        :=  Variable
+//!             |   Constant
+//!             |   Lambda Variable { Variable } ( '.' | '->' ) Expression
+//!             |   '(' Expression ')'
+//!             |   '(' Expression ',' Expression { ',' Expression } ')'
+//!             |   '[' [ Expression { ',' Expression } ] ']'
+//!             |   'let' Binding 'in' Expression
+//!             |   'let' '(' Variable ',' Variable { ',' Variable } ')' '=' Expression 'in' Expression
+//!             |   'let' 'rec' Binding { 'and' Binding } 'in' Expression
+//!             |   'if' Expression 'then' Expression 'else' Expression
+//! 
+//! Binding     :=  Variable { Variable } '=' Expression
+//! 
+//! Lambda      :=  '\' | '^' | 'λ'
+//! 
+//! Variable    :=  Identifier
+//! 
+//! Constant    :=  Integer
+//!             |   String
+//!             |   Character
+//!             |   'true' | 'false'
+//!             |   'nil'
+//!             |   'fix' | 'fst' | 'snd'
+//!             |   'cons' | 'head' | 'tail' | 'isnil' | 'map' | 'fold' | 'length'
+//!             |   'concat' | 'charAt' | 'show'
+//! 
+//! BinaryOperator := '+' | '-' | '*' | '/' | '%' | '::' | '==' | '!=' | '<' | '<=' | '>' | '>=' | '&&' | '||'
+//! 
+//! Application binds tightest, then unary minus and 'not', then '*', '/' and '%', then '+' 
+//! and '-', then '::', then comparisons, then '&&' and lastly '||'. Binary operators 
+//! associate to the left, so "1 - 2 * 3 - 4" is parsed as "(1 - (2 * 3)) - 4", and "x + f y"
+//! as "x + (f y)", except for '::', which associates to the right: "1 :: 2 :: xs" is 
+//! "1 :: (2 :: xs)".
+//! Abstractions, let expressions and conditionals extend as far right as possible.
+//! 
+//! "not e" is shorthand for "if e then false else true".
+//! 
+//! An abstraction over several variables is shorthand for nested abstractions, so "\x y. e"
+//! is "\x. \y. e", and a let with parameters binds a function: "let f x y = e in b" is 
+//! "let f = \x y. e in b". The variables bound by a recursive let are in scope in every
+//! bound expression, so that "let rec f n = ... f (n - 1) ... in f 10" and mutually 
+//! recursive functions joined by 'and' can be written. The primitive 'fix' takes the
+//! fixpoint of a function, so "fix (\f n. e)" is the function f defined by e.
+//! 
+//! A tuple "(a, b, c)" has two or more components, numbered from 0: "t.1" is the second 
+//! component of t, and "fst t" and "snd t" are shorthand for "t.0" and "t.1". Projection 
+//! binds tighter than application, so "f t.0" is "f (t.0)". "let (x, y) = t in e" binds 
+//! x and y to the components of the pair t.
+//! 
+//! A list "[a, b, c]" is shorthand for "a :: b :: c :: nil", where "h :: t" is the list with
+//! head h and tail t, as is "cons h t". "[]" is the empty list, nil.
+//! 
+//! Identifier  :=  Letter { Letter | Digit | '_' | '\'' }, where letters and digits may be
+//!                 from any script; λ always starts an abstraction
+//! 
+//! Integer     :=  [0-9]+
+//! 
+//! String      :=  '"' { Character } '"', on one line, where a character is any but '"' 
+//!                 and '\', or an escape (see lexer)
+//! 
+//! Character   :=  "'" Character "'"
+//! 
+//! Comments start with '--' and run to the end of the line, or are enclosed in '{-' and '-}',
+//! which may be nested. The lexer keeps them off the token stream.
+//! 
+//! The parser recovers from syntax errors so that every error in the input is reported
+//! at once. A malformed parenthesised expression, tuple component or list element, bound 
+//! expression of a let, or condition or consequent of a conditional, is skipped up to its 
+//! closing ')', ']' or ',', 'in' or 'and', 'then' or 'else'; a malformed statement is skipped up to the start of the next definition or statement. Whatever was 
+//! skipped is left in the syntax tree as an error node.
+//!  
 
 use crate::lexer::{
     Token, 
@@ -38,25 +107,63 @@ use crate::ast::{
     AbstractionNode, 
     ApplicationNode, 
     ArithmeticNode, 
-    LetNode
+    LetNode,
+    LetRecNode,
+    LetTupleNode,
+    BindingNode,
+    ConditionalNode,
+    TupleNode,
+    ProjectionNode,
+    ListNode,
+    Constant,
+    ErrorNode,
+    Statement
 };
 
+use crate::diagnostic::{
+    Diagnostic,
+    ErrorCode,
+    Span
+};
+use crate::error::AlabastaError;
+
 use std::cell::RefCell;
 use std::rc::Rc;
 
 pub struct Parser {
     token_list: Vec::<Token>,
     position: usize,
+    // Where the statement being parsed ends: the parser sees no tokens from here on
+    statement_end: usize,
+    end_of_input: Span,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Parser {
     pub fn new(token_list: Vec::<Token>) -> Parser {
+        // Errors at the end of the input point just past the last token
+        let end_of_input = token_list.last()
+            .map(Parser::after)
+            .unwrap_or(Span::new(0, 1, 1, 1));
+
         Parser {
+            statement_end: token_list.len(),
             token_list,
             position: 0,
+            end_of_input,
+            diagnostics: Vec::new(),
         }
     }
 
+    // The span just past a token
+    fn after(token: &Token) -> Span {
+        Span::new(
+            token.char_end, 
+            token.char_end + 1, 
+            token.line_number, 
+            token.column + token.char_end - token.char_start)
+    }
+
     fn next(&mut self) -> Option<Token> {
         let token = self.peek();
         
@@ -66,7 +173,7 @@ impl Parser {
     }
 
     fn peek(&self) -> Option<Token> {
-        if self.position >= self.token_list.len() {
+        if self.position >= self.statement_end {
             return None;
         }
 
@@ -74,192 +181,816 @@ impl Parser {
             return Some(token.clone());
         }
 
-        return None;
+        None
     }
 
-    fn expect(&mut self, token_kind: Lexeme) -> Option<Token> {
-        let token = self.peek()?.token_type.clone();
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        match self.position + offset {
+            position if position >= self.statement_end => None,
+            position => self.token_list.get(position),
+        }
+    }
 
-        match (token, token_kind) {
-            (Lexeme::Identifier(_), Lexeme::Identifier(_)) => {
-                self.next()
-            },
-            (Lexeme::Integer(_), Lexeme::Integer(_)) => {
-                self.next()
-            },
+    // Whether the statement being parsed ends before the end of the input
+    fn is_bounded(&self) -> bool {
+        self.statement_end < self.token_list.len()
+    }
+
+    // The span of the end of the statement being parsed
+    fn end_of_statement(&self) -> Span {
+        match self.statement_end.checked_sub(1).and_then(|last| self.token_list.get(last)) {
+            Some(last) if self.is_bounded() => Parser::after(last),
+            _ => self.end_of_input,
+        }
+    }
+
+    // An "expected X, found Y" diagnostic for the next token
+    // A variable bound twice by the same binding construct
+    fn duplicate(variable: &VariableNode, construct: &str) -> AlabastaError {
+        let name = variable.name.borrow();
+
+        AlabastaError::Parse(Diagnostic::new(
+            ErrorCode::DuplicateBinder,
+            format!("`{}` is bound more than once in the same {}", name, construct),
+            format!("`{}` already bound", name),
+            variable.span))
+    }
+
+    fn error(&self, expected: &str) -> AlabastaError {
+        let diagnostic = match self.peek() {
+            Some(token) => Diagnostic::expected(expected, &token.token_type.to_string(), token.span()),
+            None if self.is_bounded() => Diagnostic::expected(expected, "end of statement", self.end_of_statement()),
+            None => Diagnostic::expected(expected, "end of input", self.end_of_input),
+        };
+
+        AlabastaError::Parse(diagnostic)
+    }
+
+    // The span of the next token, or of the end of input
+    fn next_span(&self) -> Span {
+        self.peek().map_or(self.end_of_statement(), |token| token.span())
+    }
+
+    // The last token consumed
+    fn previous(&self) -> Option<&Token> {
+        self.position.checked_sub(1)
+            .and_then(|position| self.token_list.get(position))
+    }
+
+    // The span of the last token consumed
+    fn previous_span(&self) -> Span {
+        self.previous().map_or(self.end_of_input, |token| token.span())
+    }
+
+    // Record a syntax error, unless it was already recorded by a nested construct that
+    // could not recover from it
+    fn report(&mut self, error: AlabastaError) {
+        if let AlabastaError::Parse(diagnostic) = error {
+            if self.diagnostics.last() != Some(&diagnostic) {
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    // Syntax errors as a single error, if there are any
+    fn combine(diagnostics: Vec<Diagnostic>) -> Option<AlabastaError> {
+        let mut errors: Vec<AlabastaError> = diagnostics.into_iter()
+            .map(AlabastaError::Parse)
+            .collect();
+
+        match errors.len() {
+            0 => None,
+            1 => errors.pop(),
+            _ => Some(AlabastaError::Multiple(errors)),
+        }
+    }
+
+    ///
+    /// Skip tokens up to and including one of the given closing tokens ('in', 'and', ')', 
+    /// ']', ',', 'then' or 'else'), passing over nested let expressions, parentheses, brackets
+    /// and conditionals.
+    /// Gives up, consuming nothing further, at a closing token belonging to an enclosing 
+    /// expression, at the start of a definition or at the end of input; returns whether a
+    /// closing token was found.
+    /// 
+    fn synchronise(&mut self, closing: &[Lexeme]) -> bool {
+        let mut lets = 0;
+        let mut parentheses = 0;
+        let mut ifs = 0;
+
+        while let Some(token) = self.peek() {
+            // A closing token that no nested expression accounts for
+            let outermost = match token.token_type {
+                Lexeme::In | Lexeme::And => lets == 0,
+                Lexeme::RightParen | Lexeme::RightBracket | Lexeme::Comma => parentheses == 0,
+                Lexeme::Then | Lexeme::Else => ifs == 0,
+                _ => false,
+            };
+
+            if outermost {
+                if closing.contains(&token.token_type) {
+                    self.next();
+                }
+                return closing.contains(&token.token_type);
+            }
+
+            match token.token_type {
+                Lexeme::Let | Lexeme::And => {
+                    if token.token_type == Lexeme::Let {
+                        lets += 1;
+                    }
+                    // The variable, parameters and '=' following let, let rec or and do not 
+                    // start a definition
+                    self.next();
+                    if self.peek().is_some_and(|token| token.token_type == Lexeme::Rec) {
+                        self.next();
+                    }
+                    while let Some(Lexeme::Identifier(_)) = self.peek().map(|token| token.token_type) {
+                        self.next();
+                    }
+                    continue;
+                },
+                Lexeme::In => lets -= 1,
+                Lexeme::If => ifs += 1,
+                Lexeme::Else => ifs -= 1,
+                Lexeme::LeftParen | Lexeme::LeftBracket => parentheses += 1,
+                Lexeme::RightParen | Lexeme::RightBracket => parentheses -= 1,
+                _ if self.at_definition() => return false,
+                _ => { }
+            }
+
+            self.next();
+        }
+
+        false
+    }
+
+    // Skip tokens up to the start of the next definition or statement
+    fn synchronise_statement(&mut self, start: usize) {
+        // Always make progress, even if the statement failed on its first token
+        if self.position == start {
+            self.next();
+        }
+
+        while self.peek().is_some() && !self.at_definition() {
+            self.next();
+        }
+    }
+
+    ///
+    /// Parse an expression followed by one of the given closing tokens, which is consumed.
+    /// On a syntax error, the error is recorded and the rest of the expression skipped, 
+    /// leaving an error node in its place.
+    /// 
+    fn parse_enclosed(&mut self, closing: &[Lexeme], expected: &str) -> Result<ExpressionNode, AlabastaError> {
+        let start = self.next_span();
+
+        let result = self.parse_expression()
+            .and_then(|expression| match self.peek() {
+                Some(token) if closing.contains(&token.token_type) => {
+                    self.next();
+                    Ok(expression)
+                },
+                _ => Err(self.error(expected)),
+            });
+
+        match result {
+            Ok(expression) => Ok(expression),
+            Err(error) => {
+                self.report(error.clone());
+
+                if !self.synchronise(closing) {
+                    return Err(error);
+                }
+
+                Ok(ExpressionNode::Error(ErrorNode { span: start.to(self.previous_span()) }))
+            }
+        }
+    }
+
+    fn expect(&mut self, token_kind: Lexeme, expected: &str) -> Result<Token, AlabastaError> {
+        let token = self.peek().ok_or_else(|| self.error(expected))?;
+
+        match (token.token_type, token_kind) {
+            (Lexeme::Identifier(_), Lexeme::Identifier(_)) |
+            (Lexeme::Integer(_), Lexeme::Integer(_)) |
+            (Lexeme::Boolean(_), Lexeme::Boolean(_)) |
             (Lexeme::BinaryOperator(_), Lexeme::BinaryOperator(_)) => {
-                self.next()
+                self.next().ok_or_else(|| self.error(expected))
             },
             (one, two) if one == two => {
-                self.next()
+                self.next().ok_or_else(|| self.error(expected))
             },
             (_, _) => { 
-                None 
+                Err(self.error(expected))
             },
         }
     }
 
-    pub fn parse(&mut self) -> Result<ExpressionNode, String> {
-        self.parse_expression().map_or(Err("Unable to parse expression.".to_string()), |node| Ok(node))
+    // A definition starts with 'def', or with a variable followed by '='
+    fn at_definition(&self) -> bool {
+        match (self.peek_at(0), self.peek_at(1)) {
+            (Some(token), _) if token.token_type == Lexeme::Def => true,
+            (Some(Token { token_type: Lexeme::Identifier(_), .. }), Some(Token { token_type: Lexeme::Equals, .. })) => true,
+            _ => false,
+        }
+    }
+
+    // Whether the next token can start an expression
+    fn at_expression(&self) -> bool {
+        matches!(self.peek_at(0).map(|token| &token.token_type), 
+            Some(Lexeme::Lambda | Lexeme::LeftParen | Lexeme::LeftBracket | Lexeme::Let | Lexeme::If | 
+                 Lexeme::Identifier(_) | Lexeme::Integer(_) | Lexeme::Boolean(_) | Lexeme::String(_) | 
+                 Lexeme::Character(_) | Lexeme::Nil | Lexeme::Primitive(_)))
     }
 
-    fn parse_expression(&mut self) -> Option<ExpressionNode> {
-        let mut left = self.parse_single_expression()?;
+    ///
+    /// Parse a sequence of statements, such as the contents of a source file. Statements
+    /// need no separator: a statement ends where the next definition begins, or before the
+    /// first line after its own that starts in the leftmost column. A statement running 
+    /// over several lines therefore indents the lines after its first.
+    /// 
+    pub fn parse_program(&mut self) -> Result<Vec<Statement>, AlabastaError> {
+        let (statements, diagnostics) = self.parse_program_with_recovery();
 
-        loop {
-            if let Some(right) = self.parse_single_expression() {
-                left = ExpressionNode::Application(
-                    ApplicationNode {
-                        function: Rc::new(left),
-                        argument: Rc::new(right),
-                    }                
-                );
-            } else if let Some(arithmetic) = self.parse_binary_operation(left.clone()) {
-                left = arithmetic;
-            } else {
+        match Parser::combine(diagnostics) {
+            Some(error) => Err(error),
+            None => Ok(statements),
+        }
+    }
+
+    ///
+    /// As parse_program, but returning whatever could be parsed, with error nodes in 
+    /// place of malformed input, together with every syntax error found
+    /// 
+    pub fn parse_program_with_recovery(&mut self) -> (Vec<Statement>, Vec<Diagnostic>) {
+        let mut statements = Vec::new();
+
+        while self.position < self.token_list.len() {
+            let start = self.position;
+            self.statement_end = self.find_statement_end();
+            let span = self.next_span();
+
+            match self.parse_single_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    self.report(error);
+                    self.synchronise_statement(start);
+
+                    let span = span.to(self.previous_span());
+                    statements.push(Statement::Expression(ExpressionNode::Error(ErrorNode { span })));
+                }
+            }
+        }
+
+        self.statement_end = self.token_list.len();
+
+        (statements, std::mem::take(&mut self.diagnostics))
+    }
+
+    // The first token after the next that starts a line in the leftmost column
+    fn find_statement_end(&self) -> usize {
+        self.token_list.iter()
+            .enumerate()
+            .skip(self.position + 1)
+            .find(|(_, token)| token.column == 1)
+            .map_or(self.token_list.len(), |(index, _)| index)
+    }
+
+    ///
+    /// Parse a single statement spanning the whole input
+    /// 
+    pub fn parse_statement(&mut self) -> Result<Statement, AlabastaError> {
+        let statement = self.parse_single_statement();
+
+        match statement {
+            Ok(_) if self.peek().is_some() => {
+                let error = self.error("end of input");
+                self.report(error);
+            },
+            Ok(_) => { },
+            Err(ref error) => {
+                self.report(error.clone());
+            }
+        }
+
+        match Parser::combine(std::mem::take(&mut self.diagnostics)) {
+            Some(error) => Err(error),
+            None => statement,
+        }
+    }
+
+    fn parse_single_statement(&mut self) -> Result<Statement, AlabastaError> {
+        if self.at_definition() {
+            self.parse_definition()
+        } else {
+            self.parse().map(Statement::Expression)
+        }
+    }
+
+    fn parse_definition(&mut self) -> Result<Statement, AlabastaError> {
+        if self.peek().is_some_and(|token| token.token_type == Lexeme::Def) {
+            self.next();
+        }
+
+        let variable = self.parse_variable()?;
+
+        let _ = self.expect(Lexeme::Equals, "`=`")?;
+        let expression = self.parse_expression()?;
+
+        Ok(Statement::Definition(variable, expression))
+    }
+
+    pub fn parse(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        self.parse_expression()
+    }
+
+    fn parse_expression(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        self.parse_operation(0)
+    }
+
+    // Binding power of a binary operator; higher binds tighter
+    pub fn precedence(operator: &str) -> u8 {
+        match operator {
+            "||" => 1,
+            "&&" => 2,
+            "==" | "!=" | "<" | "<=" | ">" | ">=" => 3,
+            "::" => 4,
+            "*" | "/" | "%" => 6,
+            _ => 5,
+        }
+    }
+
+    // Whether a chain of the operator groups to the right; only '::' does
+    pub fn is_right_associative(operator: &str) -> bool {
+        operator == "::"
+    }
+
+    ///
+    /// Precedence climbing: parse a chain of operands joined by binary operators that bind
+    /// at least as tightly as min_precedence. The right operand of each operator only takes
+    /// operators binding tighter still, which makes the operator left-associative, or, for
+    /// a right-associative operator, operators binding at least as tightly.
+    /// 
+    fn parse_operation(&mut self, min_precedence: u8) -> Result<ExpressionNode, AlabastaError> {
+        let mut left = self.parse_unary()?;
+
+        while let Some(Lexeme::BinaryOperator(operator)) = self.peek().map(|token| token.token_type) {
+            let precedence = Parser::precedence(&operator);
+
+            if precedence < min_precedence {
                 break;
             }
+
+            self.next();
+
+            let right = match Parser::is_right_associative(&operator) {
+                true => self.parse_operation(precedence)?,
+                false => self.parse_operation(precedence + 1)?,
+            };
+            let span = left.span().to(right.span());
+
+            left = ExpressionNode::Arithmetic(
+                ArithmeticNode {
+                    operator,
+                    left: Rc::new(left),
+                    right: Rc::new(right),
+                    span,
+                }
+            );
+        }
+
+        Ok(left)
+    }
+
+    ///
+    /// Unary minus negates an integer literal, and otherwise subtracts from zero. Negation
+    /// with not chooses between the two booleans.
+    /// 
+    fn parse_unary(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let negation = match self.peek().map(|token| token.token_type) {
+            Some(Lexeme::BinaryOperator(operator)) if operator == "-" => false,
+            Some(Lexeme::Not) => true,
+            _ => return self.parse_application(),
+        };
+
+        let start = self.next_span();
+        self.next();
+
+        let operand = self.parse_unary()?;
+        let span = start.to(operand.span());
+
+        if negation {
+            return Ok(ExpressionNode::Conditional(
+                ConditionalNode {
+                    condition: Rc::new(operand),
+                    consequent: Rc::new(ExpressionNode::Constant(ConstantNode { value: Constant::Boolean(false), span: start })),
+                    alternative: Rc::new(ExpressionNode::Constant(ConstantNode { value: Constant::Boolean(true), span: start })),
+                    span,
+                }
+            ));
+        }
+
+        match operand {
+            ExpressionNode::Constant(ConstantNode { value: Constant::Integer(value), .. }) => {
+                Ok(ExpressionNode::Constant(ConstantNode { value: Constant::Integer(-value), span }))
+            },
+            operand => {
+                Ok(ExpressionNode::Arithmetic(
+                    ArithmeticNode {
+                        operator: "-".to_string(),
+                        left: Rc::new(ExpressionNode::Constant(ConstantNode { value: Constant::Integer(0), span: start })),
+                        right: Rc::new(operand),
+                        span,
+                    }
+                ))
+            }
+        }
+    }
+
+    // Application is left-associative: "x y z" is parsed as "(x y) z"
+    fn parse_application(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let mut left = self.parse_single_expression()?;
+
+        while self.at_expression() && !self.at_definition() {
+            let right = self.parse_single_expression()?;
+            let span = left.span().to(right.span());
+
+            left = ExpressionNode::Application(
+                ApplicationNode {
+                    function: Rc::new(left),
+                    argument: Rc::new(right),
+                    span,
+                }                
+            );
         }
     
-        Some(left)
+        Ok(left)
     }
 
-    fn parse_let_expression(&mut self) -> Option<ExpressionNode> {
-        let _ = self.expect(Lexeme::Let)?;
+    fn parse_let_expression(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let start = self.expect(Lexeme::Let, "`let`")?.span();
+
+        if self.peek().is_some_and(|token| token.token_type == Lexeme::Rec) {
+            return self.parse_let_rec_expression(start);
+        }
+
+        if self.peek().is_some_and(|token| token.token_type == Lexeme::LeftParen) {
+            return self.parse_let_tuple_expression(start);
+        }
         
-        let variable = match self.parse_variable()? {
-            ExpressionNode::Variable(variable) => { 
-                variable
-            },
-            _ => { return None; }
-        };
+        let variable = self.parse_variable()?;
+        let parameters = self.parse_parameters()?;
+        let function = !parameters.is_empty();
         
-        let _ = self.expect(Lexeme::Equals)?;
-        let expression  = self.parse_expression()?;
+        let _ = self.expect(Lexeme::Equals, "`=`")?;
+        let expression = self.parse_enclosed(&[Lexeme::In], "`in`")?;
+        let expression = Parser::curry(parameters, expression);
 
-        let _ = self.expect(Lexeme::In)?;
         let scope = self.parse_expression()?;
+        let span = start.to(scope.span());
 
-        Some(ExpressionNode::Let(
+        Ok(ExpressionNode::Let(
             LetNode {
-                variable: variable,
+                variable,
+                expression: Rc::new(expression),
+                scope: Rc::new(scope),
+                function,
+                span,
+            }
+        ))
+    }
+
+    // let rec, following the let at the given span; bindings are separated by 'and'
+    fn parse_let_rec_expression(&mut self, start: Span) -> Result<ExpressionNode, AlabastaError> {
+        let _ = self.expect(Lexeme::Rec, "`rec`")?;
+
+        let mut bindings: Vec<BindingNode> = Vec::new();
+
+        loop {
+            let variable = self.parse_variable()?;
+
+            if bindings.iter().any(|binding| binding.variable.name == variable.name) {
+                return Err(Parser::duplicate(&variable, "`let rec`"));
+            }
+
+            let parameters = self.parse_parameters()?;
+            let function = !parameters.is_empty();
+
+            let _ = self.expect(Lexeme::Equals, "`=`")?;
+            let expression = self.parse_enclosed(&[Lexeme::And, Lexeme::In], "`and` or `in`")?;
+            let expression = Parser::curry(parameters, expression);
+
+            bindings.push(BindingNode {
+                variable,
                 expression: Rc::new(expression),
+                function,
+            });
+
+            if self.previous().is_some_and(|token| token.token_type == Lexeme::In) {
+                break;
+            }
+        }
+
+        let scope = self.parse_expression()?;
+        let span = start.to(scope.span());
+
+        Ok(ExpressionNode::LetRec(
+            LetRecNode {
+                bindings,
                 scope: Rc::new(scope),
+                span,
             }
         ))
     }
 
-    fn parse_single_expression(&mut self) -> Option<ExpressionNode> {        
-        let token = self.peek()?;
+    // let with a tuple pattern, following the let at the given span
+    fn parse_let_tuple_expression(&mut self, start: Span) -> Result<ExpressionNode, AlabastaError> {
+        let _ = self.expect(Lexeme::LeftParen, "`(`")?;
 
-        let expression = match token.token_type {
+        let mut variables = vec![self.parse_variable()?];
+        let _ = self.expect(Lexeme::Comma, "`,`")?;
+
+        loop {
+            let variable = self.parse_variable()?;
+
+            if variables.iter().any(|other| other.name == variable.name) {
+                return Err(Parser::duplicate(&variable, "tuple pattern"));
+            }
+
+            variables.push(variable);
+
+            if self.peek().is_none_or(|token| token.token_type != Lexeme::Comma) {
+                break;
+            }
+
+            let _ = self.expect(Lexeme::Comma, "`,`")?;
+        }
+
+        let _ = self.expect(Lexeme::RightParen, "`,` or `)`")?;
+        let _ = self.expect(Lexeme::Equals, "`=`")?;
+        let expression = self.parse_enclosed(&[Lexeme::In], "`in`")?;
+
+        let scope = self.parse_expression()?;
+        let span = start.to(scope.span());
+
+        Ok(ExpressionNode::LetTuple(
+            LetTupleNode {
+                variables,
+                expression: Rc::new(expression),
+                scope: Rc::new(scope),
+                span,
+            }
+        ))
+    }
+
+    fn parse_conditional(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let start = self.expect(Lexeme::If, "`if`")?.span();
+
+        let condition = self.parse_enclosed(&[Lexeme::Then], "`then`")?;
+        let consequent = self.parse_enclosed(&[Lexeme::Else], "`else`")?;
+        let alternative = self.parse_expression()?;
+        let span = start.to(alternative.span());
+
+        Ok(ExpressionNode::Conditional(
+            ConditionalNode {
+                condition: Rc::new(condition),
+                consequent: Rc::new(consequent),
+                alternative: Rc::new(alternative),
+                span,
+            }
+        ))
+    }
+
+    // An atom followed by any number of projections: t.0.1 is (t.0).1
+    fn parse_single_expression(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let mut tuple = self.parse_atom()?;
+
+        while let (Some(Lexeme::Dot), Some(Lexeme::Integer(_))) = 
+            (self.peek_at(0).map(|token| &token.token_type), self.peek_at(1).map(|token| &token.token_type)) {
+            let _ = self.expect(Lexeme::Dot, "`.`")?;
+            let component = self.expect(Lexeme::Integer(0), "a component number")?;
+
+            let index = match component.token_type {
+                Lexeme::Integer(value) => usize::try_from(value).map_err(|_| self.error("a component number"))?,
+                _ => return Err(self.error("a component number")),
+            };
+            let span = tuple.span().to(component.span());
+
+            tuple = ExpressionNode::Projection(
+                ProjectionNode {
+                    tuple: Rc::new(tuple),
+                    index,
+                    span,
+                }
+            );
+        }
+
+        Ok(tuple)
+    }
+
+    fn parse_atom(&mut self) -> Result<ExpressionNode, AlabastaError> {        
+        let token = self.peek().ok_or_else(|| self.error("an expression"))?;
+
+        match token.token_type {
             Lexeme::Lambda => self.parse_abstraction(),
             Lexeme::LeftParen => self.parse_subexpression(),
+            Lexeme::LeftBracket => self.parse_list(),
             Lexeme::Let => self.parse_let_expression(),
-            Lexeme::Identifier(_) | Lexeme::Integer (_) => self.parse_arithmetic(),
-            _ => { None },
-        };
+            Lexeme::If => self.parse_conditional(),
+            Lexeme::Identifier(_) => self.parse_variable().map(ExpressionNode::Variable),
+            Lexeme::Integer(_) | Lexeme::Boolean(_) | Lexeme::String(_) | Lexeme::Character(_) | 
+            Lexeme::Nil | Lexeme::Primitive(_) => self.parse_constant(),
+            _ => Err(self.error("an expression")),
+        }
+    }
+
+    // A parenthesised expression, or a tuple if its components are separated by commas
+    fn parse_subexpression(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let start = self.expect(Lexeme::LeftParen, "`(`")?.span();
+
+        let mut elements = Vec::new();
+
+        loop {
+            let element = self.parse_enclosed(&[Lexeme::Comma, Lexeme::RightParen], "`,` or `)`")?;
+            elements.push(element);
+
+            if self.previous().is_some_and(|token| token.token_type == Lexeme::RightParen) {
+                break;
+            }
+        }
 
-        expression
+        if elements.len() == 1 {
+            return Ok(elements.remove(0));
+        }
+
+        Ok(ExpressionNode::Tuple(
+            TupleNode {
+                elements: elements.into_iter().map(Rc::new).collect(),
+                span: start.to(self.previous_span()),
+            }
+        ))
     }
 
-    fn parse_subexpression(&mut self) -> Option<ExpressionNode> {
-        let _ = self.expect(Lexeme::LeftParen)?;
-        let expression = self.parse_expression()?;
-        let _ = self.expect(Lexeme::RightParen)?;
+    // A list of elements separated by commas, which may be empty
+    fn parse_list(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let start = self.expect(Lexeme::LeftBracket, "`[`")?.span();
+
+        let mut elements = Vec::new();
+
+        if self.peek().is_some_and(|token| token.token_type == Lexeme::RightBracket) {
+            self.next();
+        } else {
+            loop {
+                let element = self.parse_enclosed(&[Lexeme::Comma, Lexeme::RightBracket], "`,` or `]`")?;
+                elements.push(Rc::new(element));
 
-        Some(expression)
+                if self.previous().is_some_and(|token| token.token_type == Lexeme::RightBracket) {
+                    break;
+                }
+            }
+        }
+
+        Ok(ExpressionNode::List(
+            ListNode {
+                elements,
+                span: start.to(self.previous_span()),
+            }
+        ))
     }
 
-    fn parse_abstraction(&mut self) -> Option<ExpressionNode> {
-        let _ = self.expect(Lexeme::Lambda)?;
+    fn parse_abstraction(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let start = self.expect(Lexeme::Lambda, "`\\`")?.span();
 
-        let variable = match self.parse_variable()? {
-            ExpressionNode::Variable(variable) => { 
-                variable
-            },
-            _ => { return None; }
-        };
+        let mut parameters = vec![self.parse_variable()?];
+        parameters.append(&mut self.parse_parameters()?);
         
-        let _ = self.expect(Lexeme::Dot)?;
+        // The body follows a '.' or, as in Haskell, an arrow
+        if self.peek().is_some_and(|token| token.token_type == Lexeme::Arrow) {
+            let _ = self.expect(Lexeme::Arrow, "`->`")?;
+        } else {
+            let _ = self.expect(Lexeme::Dot, "`.` or `->`")?;
+        }
         let expression = self.parse_expression()?;
+
+        let mut abstraction = Parser::curry(parameters, expression);
+        if let ExpressionNode::Abstraction(node) = &mut abstraction {
+            node.span = start.to(node.span);
+        }
     
-        return Some(ExpressionNode::Abstraction(
-            AbstractionNode {
-                variable: Rc::new(variable),
-                expression: Rc::new(expression),
-            }
-        ));
+        Ok(abstraction)
+    }
+
+    // The variables up to the '.' of an abstraction or the '=' of a let, if any
+    fn parse_parameters(&mut self) -> Result<Vec<VariableNode>, AlabastaError> {
+        let mut parameters = Vec::new();
+
+        while let Some(Lexeme::Identifier(_)) = self.peek().map(|token| token.token_type) {
+            parameters.push(self.parse_variable()?);
+        }
+
+        Ok(parameters)
+    }
+
+    ///
+    /// Nest an abstraction for each parameter around a body: \x y. e is \x. \y. e. Every
+    /// abstraction but the innermost is marked curried, so that the binder list written
+    /// can be printed again.
+    /// 
+    fn curry(parameters: Vec<VariableNode>, body: ExpressionNode) -> ExpressionNode {
+        parameters.into_iter().rev().enumerate().fold(body, |body, (index, variable)| {
+            let span = variable.span.to(body.span());
+
+            ExpressionNode::Abstraction(
+                AbstractionNode {
+                    variable: Rc::new(variable),
+                    expression: Rc::new(body),
+                    curried: index > 0,
+                    span,
+                }
+            )
+        })
     }
     
-    fn parse_binary_operation(&mut self, left: ExpressionNode) -> Option<ExpressionNode> {
-        let operator = self.expect(Lexeme::BinaryOperator(String::new()))?;
+    fn parse_variable(&mut self) -> Result<VariableNode, AlabastaError> {
+        let identifier = self.expect(Lexeme::Identifier(String::new()), "a variable")?;
+        let span = identifier.span();
 
-        if let Lexeme::BinaryOperator(value) = operator.token_type {
-            let right = self.parse_single_expression()?;
-            let expression = ExpressionNode::Arithmetic(ArithmeticNode {
-                operator: value,
-                left: Rc::new(left),
-                right: Rc::new(right),
-            });
-            Some(expression)
-        } else {
-            None
+        match identifier.token_type {
+            Lexeme::Identifier(value) => Ok(VariableNode { 
+                name: Rc::new(RefCell::new(value)), 
+                span,
+            }),
+            _ => Err(self.error("a variable")),
         }
     }
 
-    fn parse_arithmetic(&mut self) -> Option<ExpressionNode> {
-        let left = match self.peek()?.token_type {
-            Lexeme::Identifier(_) => self.parse_variable()?,
-            Lexeme::Integer(_) => self.parse_constant()?,
-            _ => { return None; }
+    fn parse_constant(&mut self) -> Result<ExpressionNode, AlabastaError> {
+        let constant = self.next().ok_or_else(|| self.error("a constant"))?;
+        let span = constant.span();
+
+        let value = match constant.token_type {
+            Lexeme::Integer(value) => Constant::Integer(value),
+            Lexeme::Boolean(value) => Constant::Boolean(value),
+            Lexeme::String(value) => Constant::String(value),
+            Lexeme::Character(value) => Constant::Character(value),
+            Lexeme::Nil => Constant::Nil,
+            Lexeme::Primitive(primitive) => Constant::Primitive(primitive),
+            _ => return Err(self.error("a constant")),
         };
 
-        if let Some(operator) = self.expect(Lexeme::BinaryOperator(String::new())) {
-            match operator.token_type {
-                Lexeme::BinaryOperator(value) => {
-                    let right = match self.peek()?.token_type {
-                        Lexeme::Identifier(_) => self.parse_variable()?,
-                        Lexeme::Integer(_) => self.parse_constant()?,
-                        _ => { return None }
-                    };
-
-                    return Some(ExpressionNode::Arithmetic(
-                        ArithmeticNode {
-                            operator: value,
-                            left: Rc::new(left),
-                            right: Rc::new(right),
-                        }
-                    ));
-                }
-                _ => { return None; }
-            }
-        } 
-        
-        Some(left)
+        Ok(ExpressionNode::Constant(ConstantNode { value, span }))
     }
+}
 
-    fn parse_variable(&mut self) -> Option<ExpressionNode> {
-        if let Some(identifier) = self.expect(Lexeme::Identifier(String::new())) {
-            if let Lexeme::Identifier(value) = identifier.token_type {
-                Some(ExpressionNode::Variable(VariableNode { name: Rc::new(RefCell::new(value))}))
-            } else {
-                None
-            }
-        } else {
-            None
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn program(source: &str) -> Vec<Statement> {
+        let token_list = Lexer::new(source.to_string()).scan().unwrap();
+
+        Parser::new(token_list).parse_program().unwrap()
+    }
+
+    fn defined(statement: &Statement) -> Option<String> {
+        match statement {
+            Statement::Definition(variable, _) => Some(variable.name.borrow().clone()),
+            Statement::Expression(_) => None,
         }
     }
 
-    fn parse_constant(&mut self) -> Option<ExpressionNode> {
-        if let Some(integer) = self.expect(Lexeme::Integer(0)) {
-            if let Lexeme::Integer(value) = integer.token_type {
-                Some(ExpressionNode::Constant(ConstantNode { value }))
-            } else {
-                None
-            }
-        } else {
-            None
+    #[test]
+    fn main_expression_follows_definitions() {
+        let statements = program("def a = 1\nb = \\x.\n    x + a\nb a + 1\n");
+
+        assert_eq!(statements.iter().map(defined).collect::<Vec<_>>(), [Some("a".to_string()), Some("b".to_string()), None]);
+        assert!(matches!(&statements[2], Statement::Expression(ExpressionNode::Arithmetic(_))));
+    }
+
+    fn error(source: &str) -> Diagnostic {
+        let token_list = Lexer::new(source.to_string()).scan().unwrap();
+
+        match Parser::new(token_list).parse_program() {
+            Err(AlabastaError::Parse(diagnostic)) => diagnostic,
+            other => panic!("`{}` should not parse: {:?}", source, other),
         }
     }
+
+    #[test]
+    fn let_rec_binds_each_name_once() {
+        let diagnostic = error("let rec f = 1 and g = 2 and f = 3 in f");
+
+        assert_eq!(diagnostic.code, ErrorCode::DuplicateBinder);
+        assert_eq!((diagnostic.span.start, diagnostic.span.end), (28, 29));
+    }
+
+    #[test]
+    fn tuple_patterns_bind_each_name_once() {
+        let diagnostic = error("let (x, y, x) = (1, 2, 3) in x");
+
+        assert_eq!(diagnostic.code, ErrorCode::DuplicateBinder);
+        assert_eq!((diagnostic.span.start, diagnostic.span.end), (11, 12));
+    }
 }
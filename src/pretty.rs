@@ -1,71 +1,630 @@
-use crate::ast::ExpressionNode;
+//!
+//! Pretty printing
+//!
+//! Expressions are rendered with as few parentheses as the grammar allows (see parser), so that
+//! the text parses back to the same expression:
+//!
+//!     - application is left-associative, so "f x y" is "(f x) y" and "f (g x)" keeps its parentheses
+//!     - binary operators follow their precedence and associate to the left, except for
+//!       "::", which associates to the right
+//!     - abstractions, let expressions and conditionals extend as far right as possible, so 
+//!       they are enclosed unless nothing follows them, as in "f (λx. x) y" and "f λx. x"
+//!     - a negative constant reads as unary minus, so it is enclosed when applied or an argument
+//!     - "0 - e" is shown as "-e", as the parser reads unary minus, unless e is a literal
+//!       the parser would negate instead, as in "0 - 5"
+//!     - "if c then false else true" is shown as "not c", which binds as unary minus does
+//!     - a projection binds tighter than application, so "f t.0" is "f (t.0)" and "(f t).0"
+//!       keeps its parentheses
+//!     - a list ending in the empty list is shown as "[1, 2, 3]", any other as "1 :: 2 :: xs"
+//!
+
+use std::fmt;
+
+use crate::ast::{
+    Constant,
+    ExpressionNode
+};
+use crate::layout::Doc;
 use crate::beta::NormalExpressionNode;
+use crate::debruijn::NamelessExpressionNode;
+use crate::parser::Parser;
+
+///
+/// Context
+///
+/// How tightly the position a term is rendered in binds, from loosest to tightest. A term
+/// binding more loosely than its position is enclosed in parentheses. Operands of binary
+/// operators bind as tightly as the precedence of the operator (see parser).
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Context {
+    Expression,
+    Operator(u8),
+    Unary,
+    Function,
+    Argument,
+}
+
+impl Context {
+    // The positions of the operands of a binary operator
+    fn operands(operator: &str) -> (Context, Context) {
+        let precedence = Parser::precedence(operator);
+
+        match Parser::is_right_associative(operator) {
+            true => (Context::Operator(precedence + 1), Context::Operator(precedence)),
+            false => (Context::Operator(precedence), Context::Operator(precedence + 1)),
+        }
+    }
+}
+
+///
+/// View
+///
+/// The shape of a term as far as layout is concerned. Children are numbered in the order
+/// they appear, which is the order redex paths follow. Abstractions carry whether they
+/// were written curried, and let bindings whether they were written as functions (see ast).
+///
+enum View<'a, T> {
+    Atom(String),
+    Negative(i64),
+    Abstraction(String, &'a T, bool),
+    Let(Option<String>, &'a T, &'a T, bool),
+    LetRec(Vec<(Option<String>, &'a T, bool)>, &'a T),
+    Application(&'a T, &'a T),
+    Arithmetic(&'a T, &'a str, &'a T),
+    Conditional(&'a T, &'a T, &'a T),
+    Not(&'a T),
+    Negate(&'a T),
+    Tuple(Vec<&'a T>),
+    Projection(&'a T, usize),
+    List(Vec<&'a T>),
+}
+
+trait Layout: Sized {
+    fn view(&self) -> View<'_, Self>;
+
+    fn constant(&self) -> Option<&Constant>;
+}
+
+// The view of a constant
+fn constant<'a, T>(value: &Constant) -> View<'a, T> {
+    match value {
+        Constant::Integer(value) if *value < 0 => View::Negative(*value),
+        value => View::Atom(value.to_string()),
+    }
+}
+
+// The view of a conditional, which is shown as not when it chooses false and then true
+fn conditional<'a, T: Layout>(condition: &'a T, consequent: &'a T, alternative: &'a T) -> View<'a, T> {
+    match (consequent.constant(), alternative.constant()) {
+        (Some(Constant::Boolean(false)), Some(Constant::Boolean(true))) => View::Not(condition),
+        _ => View::Conditional(condition, consequent, alternative),
+    }
+}
+
+// The view of a binary operation, which is shown as unary minus when it subtracts from zero
+// anything but a literal the parser would have negated
+fn arithmetic<'a, T: Layout>(lhs: &'a T, operator: &'a str, rhs: &'a T) -> View<'a, T> {
+    match (lhs.constant(), operator, rhs.constant()) {
+        (Some(Constant::Integer(0)), "-", Some(Constant::Integer(value))) if *value >= 0 => View::Arithmetic(lhs, operator, rhs),
+        (Some(Constant::Integer(0)), "-", _) => View::Negate(rhs),
+        _ => View::Arithmetic(lhs, operator, rhs),
+    }
+}
+
+// The view of a cons, which is shown as a list when it ends in the empty list
+fn cons<'a, T: Layout>(head: &'a T, tail: &'a T) -> View<'a, T> {
+    match (tail.view(), tail.constant()) {
+        (View::List(mut elements), _) => {
+            elements.insert(0, head);
+            View::List(elements)
+        },
+        (_, Some(Constant::Nil)) => View::List(vec![head]),
+        _ => View::Arithmetic(head, "::", tail),
+    }
+}
+
+// The path to an element of a list, down the tails and into the head
+fn element(redex: Option<&[usize]>, index: usize) -> Option<&[usize]> {
+    (0..index).fold(redex, |path, _| child(path, 1)).and_then(|path| child(Some(path), 0))
+}
+
+// A tuple pattern, (x, y, ...)
+fn pattern(variables: &[String]) -> String {
+    format!("({})", variables.join(", "))
+}
+
+impl Layout for ExpressionNode {
+    fn view(&self) -> View<'_, Self> {
+        match self {
+            ExpressionNode::Variable(node) => View::Atom(node.name.borrow().clone()),
+            ExpressionNode::Constant(node) => constant(&node.value),
+            ExpressionNode::Abstraction(node) => {
+                View::Abstraction(node.variable.name.borrow().clone(), node.expression.as_ref(), node.curried)
+            },
+            ExpressionNode::Application(node) => View::Application(node.function.as_ref(), node.argument.as_ref()),
+            ExpressionNode::Arithmetic(node) => arithmetic(node.left.as_ref(), &node.operator, node.right.as_ref()),
+            ExpressionNode::Let(node) => {
+                View::Let(Some(node.variable.name.borrow().clone()), node.expression.as_ref(), node.scope.as_ref(), node.function)
+            },
+            ExpressionNode::LetRec(node) => {
+                let bindings = node.bindings.iter()
+                    .map(|binding| (Some(binding.variable.name.borrow().clone()), binding.expression.as_ref(), binding.function))
+                    .collect();
+
+                View::LetRec(bindings, node.scope.as_ref())
+            },
+            ExpressionNode::LetTuple(node) => {
+                let variables: Vec<String> = node.variables.iter()
+                    .map(|variable| variable.name.borrow().clone())
+                    .collect();
+
+                View::Let(Some(pattern(&variables)), node.expression.as_ref(), node.scope.as_ref(), false)
+            },
+            ExpressionNode::Conditional(node) => {
+                conditional(node.condition.as_ref(), node.consequent.as_ref(), node.alternative.as_ref())
+            },
+            ExpressionNode::Tuple(node) => View::Tuple(node.elements.iter().map(|element| element.as_ref()).collect()),
+            ExpressionNode::Projection(node) => View::Projection(node.tuple.as_ref(), node.index),
+            ExpressionNode::List(node) => View::List(node.elements.iter().map(|element| element.as_ref()).collect()),
+            ExpressionNode::Error(_) => View::Atom(String::from("<error>")),
+        }
+    }
+
+    fn constant(&self) -> Option<&Constant> {
+        match self {
+            ExpressionNode::Constant(node) => Some(&node.value),
+            _ => None,
+        }
+    }
+}
+
+impl Layout for NormalExpressionNode {
+    fn view(&self) -> View<'_, Self> {
+        match self {
+            NormalExpressionNode::Variable(name) => View::Atom(name.clone()),
+            NormalExpressionNode::Constant(value) => constant(value),
+            NormalExpressionNode::Abstraction(parameter, body, curried) => View::Abstraction(parameter.clone(), body.as_ref(), *curried),
+            NormalExpressionNode::Application(function, argument) => View::Application(function.as_ref(), argument.as_ref()),
+            NormalExpressionNode::Arithmetic(lhs, operator, rhs) => arithmetic(lhs.as_ref(), operator, rhs.as_ref()),
+            NormalExpressionNode::Let(parameter, expression, body, function) => {
+                View::Let(Some(parameter.clone()), expression.as_ref(), body.as_ref(), *function)
+            },
+            NormalExpressionNode::LetRec(bindings, body) => {
+                let bindings = bindings.iter()
+                    .map(|(parameter, expression, function)| (Some(parameter.clone()), expression.as_ref(), *function))
+                    .collect();
+
+                View::LetRec(bindings, body.as_ref())
+            },
+            NormalExpressionNode::LetTuple(parameters, expression, body) => {
+                View::Let(Some(pattern(parameters)), expression.as_ref(), body.as_ref(), false)
+            },
+            NormalExpressionNode::Conditional(condition, consequent, alternative) => {
+                conditional(condition.as_ref(), consequent.as_ref(), alternative.as_ref())
+            },
+            NormalExpressionNode::Tuple(elements) => View::Tuple(elements.iter().map(|element| element.as_ref()).collect()),
+            NormalExpressionNode::Projection(tuple, index) => View::Projection(tuple.as_ref(), *index),
+            NormalExpressionNode::Cons(head, tail) => cons(head.as_ref(), tail.as_ref()),
+        }
+    }
+
+    fn constant(&self) -> Option<&Constant> {
+        match self {
+            NormalExpressionNode::Constant(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Layout for NamelessExpressionNode {
+    fn view(&self) -> View<'_, Self> {
+        match self {
+            NamelessExpressionNode::Free(name) => View::Atom(name.clone()),
+            NamelessExpressionNode::Bound(index) => View::Atom(format!("#{}", index)),
+            NamelessExpressionNode::Constant(value) => constant(value),
+            NamelessExpressionNode::Abstraction(body) => View::Abstraction(String::new(), body.as_ref(), false),
+            NamelessExpressionNode::Application(function, argument) => View::Application(function.as_ref(), argument.as_ref()),
+            NamelessExpressionNode::Arithmetic(lhs, operator, rhs) => arithmetic(lhs.as_ref(), operator, rhs.as_ref()),
+            NamelessExpressionNode::Let(expression, body) => View::Let(None, expression.as_ref(), body.as_ref(), false),
+            NamelessExpressionNode::LetRec(expressions, body) => {
+                let bindings = expressions.iter()
+                    .map(|expression| (None, expression.as_ref(), false))
+                    .collect();
+
+                View::LetRec(bindings, body.as_ref())
+            },
+            // The variables of a pattern are shown as the indices they have in its scope
+            NamelessExpressionNode::LetTuple(arity, expression, body) => {
+                let indices: Vec<String> = (0..*arity).rev().map(|index| format!("#{}", index)).collect();
+
+                View::Let(Some(pattern(&indices)), expression.as_ref(), body.as_ref(), false)
+            },
+            NamelessExpressionNode::Conditional(condition, consequent, alternative) => {
+                conditional(condition.as_ref(), consequent.as_ref(), alternative.as_ref())
+            },
+            NamelessExpressionNode::Tuple(elements) => View::Tuple(elements.iter().map(|element| element.as_ref()).collect()),
+            NamelessExpressionNode::Projection(tuple, index) => View::Projection(tuple.as_ref(), *index),
+            NamelessExpressionNode::Cons(head, tail) => cons(head.as_ref(), tail.as_ref()),
+        }
+    }
+
+    fn constant(&self) -> Option<&Constant> {
+        match self {
+            NamelessExpressionNode::Constant(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+const HIGHLIGHT: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+///
+/// How the redex is marked: highlighted in colour, or enclosed in brackets that cannot
+/// be mistaken for the list brackets of the language where colour is not wanted
+///
+#[derive(Clone, Copy)]
+enum Mark {
+    Colour,
+    Brackets,
+}
+
+impl Mark {
+    fn around(self, doc: Doc) -> Doc {
+        match self {
+            Mark::Colour => Doc::Escape(HIGHLIGHT.to_string()).append(doc).append(Doc::Escape(RESET.to_string())),
+            Mark::Brackets => Doc::text("⟦").append(doc).append(Doc::text("⟧")),
+        }
+    }
+}
+
+// Nesting of the lines a term is broken into
+const INDENT: usize = 2;
+
+// Follow a redex path only into the child it leads to
+fn child(redex: Option<&[usize]>, index: usize) -> Option<&[usize]> {
+    redex
+        .and_then(|path| path.split_first())
+        .filter(|(head, _)| **head == index)
+        .map(|(_, tail)| tail)
+}
+
+///
+/// The head of a binding in a let, such as "f x y =" for a function, followed by the
+/// expression bound, which for a function is the body of its parameters. Nameless
+/// bindings have no head.
+///
+fn binding<'a, 'p, T: Layout>(keyword: &str, parameter: Option<String>, expression: &'a T, function: bool, redex: Option<&'p [usize]>) -> (Doc, &'a T, Option<&'p [usize]>) {
+    let mut parameters = String::new();
+    let mut expression = expression;
+    let mut path = redex;
+
+    // The parameters of a function are the binder list of the abstraction it binds
+    let mut more = function;
+
+    while let (true, View::Abstraction(name, inner, curried), false) = (more, expression.view(), matches!(path, Some([]))) {
+        parameters.push_str(&format!(" {}", name));
+
+        more = curried;
+        expression = inner;
+        path = child(path, 0);
+    }
 
-pub fn pretty_print(node: &ExpressionNode) {
-    match node {
-        ExpressionNode::Variable(node) => {
-            print!("{}", node.name.borrow());
+    let head = match parameter {
+        Some(parameter) => format!("{} {}{} =", keyword, parameter, parameters),
+        None => keyword.to_string(),
+    };
+
+    (Doc::Text(head), expression, path)
+}
+
+///
+/// Lay out a term in the given context. A term is last when nothing follows it before the
+/// end of the enclosing parentheses, which lets an abstraction or let go unenclosed.
+/// The term at the end of the redex path, if any, is marked.
+/// 
+/// A term too wide for the line breaks after the binders of an abstraction, around the
+/// bound expression of a let, before every argument of an application, before binary
+/// operators, before the branches of a conditional and after the commas of a tuple or
+/// a list, indenting what follows.
+/// 
+/// The elements of a list shown as [a, b, ...] are found down the tails of its conses,
+/// so the path to the second element is the tail and then its head.
+///
+fn layout<T: Layout>(node: &T, context: Context, last: bool, redex: Option<&[usize]>, mark: Mark) -> Doc {
+    let highlight = matches!(redex, Some([]));
+
+    let view = node.view();
+
+    let enclosed = match &view {
+        View::Atom(_) | View::Tuple(_) | View::Projection(_, _) | View::List(_) => false,
+        View::Negative(_) | View::Not(_) | View::Negate(_) => context > Context::Unary,
+        View::Abstraction(_, _, _) | View::Let(_, _, _, _) | View::LetRec(_, _) | View::Conditional(_, _, _) => !last,
+        View::Application(_, _) => context > Context::Function,
+        View::Arithmetic(_, operator, _) => context > Context::Operator(Parser::precedence(operator)),
+    };
+
+    // Whatever is enclosed is followed by nothing within its parentheses
+    let last = last || enclosed;
+
+    let doc = match view {
+        View::Atom(text) => {
+            Doc::Text(text)
         },
-        ExpressionNode::Constant(node) => {
-            print!("{}", node.value);
+        View::Negative(value) => {
+            Doc::Text(value.to_string())
         },
-        ExpressionNode::Abstraction(node) => {
-            print!("(λ{}. ", node.variable.as_ref().name.borrow().clone());
-            pretty_print(node.expression.as_ref());
-            print!(")");
+        View::Abstraction(parameter, body, curried) => {
+            // Binders of nested abstractions stay on the first line, as λx. λy. ... or, 
+            // where written curried, λx y. ...
+            let mut binders = format!("λ{}", parameter);
+            let mut curried = curried;
+            let mut body = body;
+            let mut path = child(redex, 0);
+
+            while let (View::Abstraction(parameter, inner, inner_curried), false) = (body.view(), matches!(path, Some([]))) {
+                match curried {
+                    true => binders.push_str(&format!(" {}", parameter)),
+                    false => binders.push_str(&format!(". λ{}", parameter)),
+                }
+
+                curried = inner_curried;
+                body = inner;
+                path = child(path, 0);
+            }
+
+            binders.push('.');
+
+            Doc::Text(binders)
+                .append(Doc::Line.append(layout(body, Context::Expression, last, path, mark)).nest(INDENT))
+                .group()
         },
-        ExpressionNode::Application(node) => {
-            print!("(");
-            pretty_print(node.function.as_ref());
-            print!(" ");
-            pretty_print(node.argument.as_ref());
-            print!(")");
+        View::Let(parameter, expression, body, function) => {
+            let (head, expression, path) = binding("let", parameter, expression, function, child(redex, 0));
+
+            head
+                .append(Doc::Line.append(layout(expression, Context::Expression, true, path, mark)).nest(INDENT))
+                .group()
+                .append(Doc::Line)
+                .append(Doc::text("in "))
+                .append(layout(body, Context::Expression, last, child(redex, 1), mark))
+                .group()
         },
-        ExpressionNode::Arithmetic(node) => {
-            print!("(");
-            pretty_print(node.left.as_ref());
-            print!(" {} ", node.operator);
-            pretty_print(node.right.as_ref());
-            print!(")");
+        View::LetRec(bindings, body) => {
+            // Bindings follow one another, as let rec f = ... and g = ...
+            let count = bindings.len();
+            let bindings = bindings.into_iter().enumerate()
+                .fold(Doc::Nil, |doc, (index, (parameter, expression, function))| {
+                    let keyword = if index == 0 { "let rec" } else { "and" };
+                    let (head, expression, path) = binding(keyword, parameter, expression, function, child(redex, index));
+
+                    let doc = match index {
+                        0 => doc,
+                        _ => doc.append(Doc::Line),
+                    };
+
+                    doc.append(head
+                        .append(Doc::Line.append(layout(expression, Context::Expression, true, path, mark)).nest(INDENT))
+                        .group())
+                });
+
+            bindings
+                .append(Doc::Line)
+                .append(Doc::text("in "))
+                .append(layout(body, Context::Expression, last, child(redex, count), mark))
+                .group()
         },
-        ExpressionNode::Let(node) => {
-            print!("let {} = ", node.variable.name.borrow().clone());
-            pretty_print(node.expression.as_ref());
-            print!(" in ");
-            pretty_print(node.scope.as_ref());
+        View::Application(function, argument) => {
+            // Arguments of an application spine, f a b c, break together
+            let mut arguments = vec![(argument, child(redex, 1))];
+            let mut function = function;
+            let mut path = child(redex, 0);
+
+            while let (View::Application(inner, argument), false) = (function.view(), matches!(path, Some([]))) {
+                arguments.push((argument, child(path, 1)));
+                function = inner;
+                path = child(path, 0);
+            }
+
+            let count = arguments.len();
+            let arguments = arguments.into_iter().rev().enumerate()
+                .fold(Doc::Nil, |doc, (index, (argument, path))| {
+                    doc.append(Doc::Line).append(layout(argument, Context::Argument, last && index + 1 == count, path, mark))
+                });
+
+            layout(function, Context::Function, false, path, mark)
+                .append(arguments.nest(INDENT))
+                .group()
         },
-        // _ => { }
-    }
-}
+        View::Arithmetic(lhs, operator, rhs) => {
+            let (left, right) = Context::operands(operator);
 
-pub fn pretty_print_normal(node: &NormalExpressionNode) 
-{
-    match node {
-        NormalExpressionNode::Variable(name) => {
-            print!("{}", name);
+            layout(lhs, left, false, child(redex, 0), mark)
+                .append(Doc::Line
+                    .append(Doc::text(&format!("{} ", operator)))
+                    .append(layout(rhs, right, last, child(redex, 1), mark))
+                    .nest(INDENT))
+                .group()
         },
-        NormalExpressionNode::Constant(value) => {
-            print!("{}", value);
+        View::Conditional(condition, consequent, alternative) => {
+            Doc::text("if ")
+                .append(layout(condition, Context::Expression, true, child(redex, 0), mark).nest(INDENT))
+                .append(Doc::Line)
+                .append(Doc::text("then "))
+                .append(layout(consequent, Context::Expression, true, child(redex, 1), mark).nest(INDENT))
+                .append(Doc::Line)
+                .append(Doc::text("else "))
+                .append(layout(alternative, Context::Expression, last, child(redex, 2), mark).nest(INDENT))
+                .group()
         },
-        NormalExpressionNode::Abstraction(parameter, body) => {
-            print!("(λ{}. ", parameter);
-            pretty_print_normal(body);
-            print!(")");
+        View::Not(operand) => {
+            Doc::text("not ").append(layout(operand, Context::Unary, last, child(redex, 0), mark))
+        },
+        View::Negate(operand) => {
+            // A minus sign next to another would start a comment (see lexer)
+            let context = match operand.view() {
+                View::Negative(_) | View::Negate(_) => Context::Argument,
+                _ => Context::Unary,
+            };
+
+            Doc::text("-").append(layout(operand, context, last, child(redex, 1), mark))
+        },
+        View::Tuple(elements) => {
+            let elements = elements.into_iter().enumerate()
+                .fold(Doc::Nil, |doc, (index, element)| {
+                    let doc = match index {
+                        0 => doc,
+                        _ => doc.append(Doc::text(",")).append(Doc::Line),
+                    };
+
+                    doc.append(layout(element, Context::Expression, true, child(redex, index), mark))
+                });
+
+            Doc::text("(")
+                .append(elements.nest(INDENT))
+                .append(Doc::text(")"))
+                .group()
         },
-        NormalExpressionNode::Application(function, argument) => {
-            print!("(");
-            pretty_print_normal(function);
-            print!(" ");
-            pretty_print_normal(argument);
-            print!(")");
+        View::Projection(tuple, index) => {
+            layout(tuple, Context::Argument, false, child(redex, 0), mark)
+                .append(Doc::text(&format!(".{}", index)))
         },
-        NormalExpressionNode::Arithmetic(lhs, operator, rhs) => {
-            print!("(");
-            pretty_print_normal(lhs);
-            print!(" {} ", operator);
-            pretty_print_normal(rhs);
-            print!(")");
+        View::List(elements) => {
+            let elements = elements.into_iter().enumerate()
+                .fold(Doc::Nil, |doc, (index, item)| {
+                    let doc = match index {
+                        0 => doc,
+                        _ => doc.append(Doc::text(",")).append(Doc::Line),
+                    };
+
+                    doc.append(layout(item, Context::Expression, true, element(redex, index), mark))
+                });
+
+            Doc::text("[")
+                .append(elements.nest(INDENT))
+                .append(Doc::text("]"))
+                .group()
         },
-        _ => { }
+    };
+
+    let doc = match enclosed {
+        true => Doc::text("(").append(doc).append(Doc::text(")")),
+        false => doc,
+    };
+
+    match highlight {
+        true => mark.around(doc),
+        false => doc,
+    }
+}
+
+///
+/// Render a term in the given width, starting at the given column
+///
+fn render_term<T: Layout>(node: &T, redex: Option<&[usize]>, mark: Mark, width: usize, column: usize) -> String {
+    layout(node, Context::Expression, true, redex, mark).pretty(width, column)
+}
+
+///
+/// Render an expression with the redex at the given path highlighted in colour, or
+/// enclosed in ⟦ ⟧ without it
+///
+pub fn render_redex(node: &NormalExpressionNode, path: &[usize], colour: bool, width: usize, column: usize) -> String {
+    let mark = match colour {
+        true => Mark::Colour,
+        false => Mark::Brackets,
+    };
+
+    render_term(node, Some(path), mark, width, column)
+}
+
+impl fmt::Display for ExpressionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", render_term(self, None, Mark::Colour, usize::MAX, 0))
+    }
+}
+
+impl fmt::Display for NormalExpressionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", render_term(self, None, Mark::Colour, usize::MAX, 0))
+    }
+}
+
+///
+/// Nameless expressions print bound variables as De Bruijn indices (#0, #1, ...)
+///
+impl fmt::Display for NamelessExpressionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", render_term(self, None, Mark::Colour, usize::MAX, 0))
+    }
+}
+
+///
+/// The pretty_print functions print a term after a prefix, such as a prompt, breaking it
+/// into lines that fit the width given and are indented past the prefix
+///
+pub fn pretty_print(prefix: &str, node: &ExpressionNode, width: usize) {
+    print!("{}{}", prefix, render_term(node, None, Mark::Colour, width, prefix.chars().count()));
+}
+
+pub fn pretty_print_normal(prefix: &str, node: &NormalExpressionNode, width: usize) {
+    print!("{}{}", prefix, render_term(node, None, Mark::Colour, width, prefix.chars().count()));
+}
+
+pub fn pretty_print_redex(prefix: &str, node: &NormalExpressionNode, path: &[usize], width: usize, colour: bool) {
+    print!("{}{}", prefix, render_redex(node, path, colour, width, prefix.chars().count()));
+}
+
+pub fn pretty_print_nameless(prefix: &str, node: &NamelessExpressionNode, width: usize) {
+    print!("{}{}", prefix, render_term(node, None, Mark::Colour, width, prefix.chars().count()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_redex;
+    use crate::beta::{BetaReducer, NormalExpressionNode, ReductionStrategy};
+    use crate::environment::Environment;
+
+    // Results keep the binder lists, function bindings and unary minus they were written with
+    const SUGARED: [(&str, &str); 7] = [
+        (r"\x y z. x", "λx y z. x"),
+        (r"\x. \y. x", "λx. λy. x"),
+        (r"(\a x y. a) 1", "λx y. 1"),
+        (r"let f x y = x + y in f", "λx y. x + y"),
+        (r"let rec g n = if n < 1 then n else g (n - 1) in g", "let rec g n = if n < 1 then n else g (n - 1) in λn. if n < 1 then n else g (n - 1)"),
+        (r"\x. -x", "λx. -x"),
+        (r"\x y. - -x y", "λx y. -(-x y)"),
+    ];
+
+    fn reduce(source: &str, strategy: ReductionStrategy) -> NormalExpressionNode {
+        let mut environment = Environment::new();
+        let normal = environment.term(source);
+
+        environment.restore_names(&BetaReducer::new(strategy).reduce(&normal).unwrap())
+    }
+
+    #[test]
+    fn results_print_as_written_and_parse_back() {
+        for (source, expected) in SUGARED {
+            for strategy in [ReductionStrategy::NormalOrder, ReductionStrategy::CallByNeed] {
+                let printed = reduce(source, strategy).to_string();
+                assert_eq!(printed, expected, "`{}` under {}", source, strategy);
+
+                // Already a result, the printed term reduces to itself
+                assert_eq!(reduce(&printed, strategy).to_string(), printed, "`{}` under {}", printed, strategy);
+            }
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn redexes_are_bracketed_without_colour() {
+        let mut environment = Environment::new();
+        let node = environment.term(r"(\x. x) 1 + (\y. y) 2");
+        let node = environment.restore_names(&node);
+
+        assert_eq!(render_redex(&node, &[0], false, usize::MAX, 0), "⟦(λx. x) 1⟧ + (λy. y) 2");
+        assert_eq!(render_redex(&node, &[1], false, usize::MAX, 0), "(λx. x) 1 + ⟦(λy. y) 2⟧");
+        assert_eq!(render_redex(&node, &[0], true, usize::MAX, 0), "\x1b[1;33m(λx. x) 1\x1b[0m + (λy. y) 2");
+    }
+}